@@ -96,10 +96,8 @@ impl std::str::FromStr for Rank {
     fn from_str(str: &str) -> Result<Self, Self::Err> {
         let lower_case = str.to_ascii_lowercase();
         for rank in Self::iter() {
-            if rank != Rank::LowAce {
-                if lower_case == rank.to_string().to_ascii_lowercase() {
-                    return Ok(rank);
-                }
+            if rank != Rank::LowAce && lower_case == rank.to_string().to_ascii_lowercase() {
+                return Ok(rank);
             }
         }
         return Err("Invalid Rank")
@@ -125,6 +123,10 @@ impl Card {
         Card { rank: self.rank, suit: self.suit, scoring_rank: rank }
     }
 
+    pub fn is_deuce(card: &Card) -> bool {
+        card.rank == Rank::Two
+    }
+
     pub fn is_one_eyed_jack(card: &Card) -> bool {
         card.rank == Rank::Jack
             && (card.suit == Suit::Spades || card.suit == Suit::Hearts)
@@ -134,6 +136,11 @@ impl Card {
         card.rank == Rank::King && card.suit == Suit::Hearts
     }
 
+    /// Threes and nines, wild in Baseball.
+    pub fn is_baseball_wild(card: &Card) -> bool {
+        card.rank == Rank::Three || card.rank == Rank::Nine
+    }
+
     pub fn is_joker(card: &Card) -> bool {
         card.rank == Rank::Joker
     }
@@ -198,7 +205,7 @@ impl Ord for Card {
 
 impl PartialOrd for Card {
     fn partial_cmp(&self, other: &Card) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
@@ -221,18 +228,21 @@ pub struct CardVector(pub Vec<Card>);
 
 impl std::str::FromStr for CardVector {
     type Err = ParseError;
+    /// Cards may be separated by whitespace or commas; anything else that is
+    /// not a rank followed by a suit is an error.
     fn from_str(str: &str) -> Result<Self, Self::Err> {
-        let cards = str.to_ascii_lowercase()
+        let chars = str.to_ascii_lowercase()
             .replace("10", "t")
             .chars()
-            .filter(|ch| "akqjt98765432?cdhs♥♣♦♠".contains(*ch))
-            .collect::<Vec<_>>()
-            .chunks(2)
-            .map(|chunk| {
-                let rank = chunk[0].to_string().parse::<Rank>().unwrap();
-                let suit = chunk[1].to_string().parse::<Suit>().unwrap();
-                Card::new(rank, suit)
-            }).collect::<Vec<_>>();
+            .filter(|ch| !ch.is_whitespace() && *ch != ',')
+            .collect::<Vec<_>>();
+
+        let cards = chars.chunks(2)
+            .map(|chunk| match chunk.len() {
+                2 => chunk.iter().collect::<String>().parse::<Card>(),
+                _ => Err("Invalid card")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(CardVector(cards))
     }
@@ -258,6 +268,219 @@ impl std::fmt::Display for CardVector {
     }
 }
 
+const JOKER_INDEX: usize = 52;
+
+impl Card {
+    /// Bit position of this card within a `CardSet`. Standard cards map to
+    /// 0..52 (rank-major, deuces first); every joker shares index 52.
+    pub fn index(&self) -> usize {
+        match self.rank {
+            Rank::Joker => JOKER_INDEX,
+            Rank::LowAce => (Rank::Ace as usize - 2) * 4 + self.suit as usize,
+            rank => (rank as usize - 2) * 4 + self.suit as usize
+        }
+    }
+
+    pub fn from_index(index: usize) -> Card {
+        if index == JOKER_INDEX {
+            return Card::new(Rank::Joker, Suit::Joker);
+        }
+
+        let rank = Rank::for_ordinal(index / 4 + 2);
+        let suit = Suit::iter().nth(index % 4).unwrap();
+        Card::new(rank, suit)
+    }
+}
+
+/// A set of cards stored as a 64-bit mask. Jokers are indistinguishable from
+/// one another, so a set holds at most one joker.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub const fn new() -> CardSet {
+        CardSet(0)
+    }
+
+    pub const fn from_bits(bits: u64) -> CardSet {
+        CardSet(bits)
+    }
+
+    /// All 52 standard cards.
+    pub const fn standard_deck() -> CardSet {
+        CardSet((1 << JOKER_INDEX) - 1)
+    }
+
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    pub fn insert(&mut self, card: &Card) -> bool {
+        let mask = 1 << card.index();
+        let inserted = self.0 & mask == 0;
+        self.0 |= mask;
+        return inserted;
+    }
+
+    pub fn remove(&mut self, card: &Card) -> bool {
+        let mask = 1 << card.index();
+        let removed = self.0 & mask != 0;
+        self.0 &= !mask;
+        return removed;
+    }
+
+    pub fn contains(&self, card: &Card) -> bool {
+        self.0 & (1 << card.index()) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: &CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: &CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn is_disjoint(&self, other: &CardSet) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn iter(&self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+
+    pub fn to_vec(&self) -> Vec<Card> {
+        self.iter().collect()
+    }
+}
+
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(Card::from_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.count_ones() as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl<'a> std::iter::FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item=&'a Card>>(iter: I) -> Self {
+        let mut set = CardSet::new();
+        for card in iter {
+            set.insert(card);
+        }
+        return set;
+    }
+}
+
+impl std::iter::FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item=Card>>(iter: I) -> Self {
+        let mut set = CardSet::new();
+        for card in iter {
+            set.insert(&card);
+        }
+        return set;
+    }
+}
+
+impl From<&[Card]> for CardSet {
+    fn from(cards: &[Card]) -> Self {
+        cards.iter().collect()
+    }
+}
+
+impl From<&CardVector> for CardSet {
+    fn from(cards: &CardVector) -> Self {
+        cards.iter().collect()
+    }
+}
+
+impl From<CardSet> for CardVector {
+    fn from(set: CardSet) -> Self {
+        CardVector(set.to_vec())
+    }
+}
+
+impl std::ops::BitOr for CardSet {
+    type Output = CardSet;
+    fn bitor(self, other: CardSet) -> CardSet {
+        self.union(&other)
+    }
+}
+
+impl std::ops::BitOrAssign for CardSet {
+    fn bitor_assign(&mut self, other: CardSet) {
+        self.0 |= other.0;
+    }
+}
+
+impl std::ops::BitAnd for CardSet {
+    type Output = CardSet;
+    fn bitand(self, other: CardSet) -> CardSet {
+        self.intersection(&other)
+    }
+}
+
+impl std::ops::BitAndAssign for CardSet {
+    fn bitand_assign(&mut self, other: CardSet) {
+        self.0 &= other.0;
+    }
+}
+
+impl std::ops::Sub for CardSet {
+    type Output = CardSet;
+    fn sub(self, other: CardSet) -> CardSet {
+        self.difference(&other)
+    }
+}
+
+impl std::ops::SubAssign for CardSet {
+    fn sub_assign(&mut self, other: CardSet) {
+        self.0 &= !other.0;
+    }
+}
+
+impl std::fmt::Display for CardSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", fmt(self.to_vec().iter()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -356,6 +579,15 @@ mod tests {
         assert_eq!(cards[1], King.of(Diamonds));
     }
 
+    #[test]
+    fn test_invalid_cards_parsing() {
+        assert_eq!("Ac, 10d".parse::<CardVector>().unwrap().to_vec(), vec![Ace.of(Clubs), Ten.of(Diamonds)]);
+        assert!("AcZd".parse::<CardVector>().is_err());
+        assert!("AcKx".parse::<CardVector>().is_err());
+        assert!("AcK".parse::<CardVector>().is_err());
+        assert!("Ac-Kd".parse::<CardVector>().is_err());
+    }
+
     #[test]
     fn test_cards_parsing_with_commas() {
         let cards = "Ac,Kd".parse::<CardVector>().unwrap();
@@ -388,4 +620,53 @@ mod tests {
         assert!(!King.of(Hearts).is_wild(&None));
         assert!(!Jack.of(Hearts).is_wild(&None));
     }
+
+    #[test]
+    fn test_card_index_round_trip() {
+        for index in 0..53 {
+            assert_eq!(Card::from_index(index).index(), index);
+        }
+        assert_eq!(Two.of(Clubs).index(), 0);
+        assert_eq!(Ace.of(Spades).index(), 51);
+        assert_eq!(Rank::Joker.of(Suit::Joker).index(), 52);
+        assert_eq!(Ace.of(Hearts).scored_as(LowAce).index(), Ace.of(Hearts).index());
+    }
+
+    #[test]
+    fn test_card_set() {
+        let mut set = CardSet::from(&CardVector::parse("Ac Kd 2s"));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&Ace.of(Clubs)));
+        assert!(!set.contains(&Ace.of(Diamonds)));
+
+        assert!(set.insert(&Ace.of(Diamonds)));
+        assert!(!set.insert(&Ace.of(Diamonds)));
+        assert_eq!(set.len(), 4);
+
+        assert!(set.remove(&King.of(Diamonds)));
+        assert!(!set.remove(&King.of(Diamonds)));
+        assert_eq!(set.to_vec(), vec![Two.of(Spades), Ace.of(Clubs), Ace.of(Diamonds)]);
+    }
+
+    #[test]
+    fn test_card_set_operations() {
+        let a = CardSet::from(&CardVector::parse("Ac Kd Qh"));
+        let b = CardSet::from(&CardVector::parse("Kd Qh Js"));
+
+        assert_eq!(a | b, CardSet::from(&CardVector::parse("Ac Kd Qh Js")));
+        assert_eq!(a & b, CardSet::from(&CardVector::parse("Kd Qh")));
+        assert_eq!(a - b, CardSet::from(&CardVector::parse("Ac")));
+        assert!(!a.is_disjoint(&b));
+        assert!((a - b).is_disjoint(&b));
+
+        assert_eq!(CardSet::standard_deck().len(), 52);
+        assert_eq!(CardSet::standard_deck().iter().count(), 52);
+        assert!(CardSet::new().is_empty());
+    }
+
+    #[test]
+    fn test_card_set_to_card_vector() {
+        let cards = CardVector::from(CardSet::from(&CardVector::parse("Kd 2c")));
+        assert_eq!(cards.to_vec(), vec![Two.of(Clubs), King.of(Diamonds)]);
+    }
 }
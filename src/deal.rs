@@ -0,0 +1,323 @@
+//! Dealing the rest of several partial hands from a shared deck, for games
+//! without a community board such as stud and draw, and for community card
+//! deals with random pockets.
+
+use itertools::Itertools;
+
+use super::card::{Card, CardSet};
+use super::monte_carlo::{for_each_draw, MonteCarloOptions};
+use super::suit_symmetry::{SuitPermutation, stabilizer};
+
+/// `CardSet` bits come in levels of four, one per rank from the twos up,
+/// with the jokers alone on the level above the aces.
+const TOP_LEVEL: u32 = 13;
+
+fn choose(n: usize, k: usize) -> Option<u64> {
+    if k > n {
+        return Some(0);
+    }
+
+    let mut result = 1u64;
+    for i in 0..k as u64 {
+        result = result.checked_mul(n as u64 - i)? / (i + 1);
+    }
+    return Some(result);
+}
+
+/// Number of ways to deal `needed[i]` cards to each hand in turn from
+/// `remaining` cards, or `None` when it does not fit in a `u64`.
+pub fn deal_count(remaining: usize, needed: &[usize]) -> Option<u64> {
+    let mut remaining = remaining;
+    let mut count = 1u64;
+    for n in needed {
+        count = count.checked_mul(choose(remaining, *n)?)?;
+        remaining = remaining.saturating_sub(*n);
+    }
+    return Some(count);
+}
+
+fn deal_rest<V>(deck: &[Card], needed: &[usize], hand: usize, used: CardSet, hands: &mut Vec<CardSet>, visit: &mut V)
+where V: FnMut(&[CardSet]) {
+    if hand == needed.len() {
+        visit(hands);
+        return;
+    }
+
+    let known = hands[hand];
+    for combination in deck.iter().filter(|card| !used.contains(card)).combinations(needed[hand]) {
+        let drawn = combination.into_iter().collect::<CardSet>();
+        hands[hand] = known | drawn;
+        deal_rest(deck, needed, hand + 1, used | drawn, hands, visit);
+    }
+    hands[hand] = known;
+}
+
+/// Visits every way of dealing `needed[i]` more cards from `deck` to the
+/// hand `known[i]`, striping the first card dealt across `threads` so each
+/// thread only generates its own deals. Each thread accumulates into its
+/// own value from `init`; the accumulators are returned in stripe order so
+/// merging is deterministic. `deck` must not contain any of the known cards.
+pub fn for_each_deal<T, I, V>(deck: &[Card], known: &[CardSet], needed: &[usize], threads: usize, init: I, visit: V) -> Vec<T>
+where T: Send, I: Fn() -> T + Sync, V: Fn(&mut T, &[CardSet]) + Sync {
+    let first = needed.iter().position(|n| *n > 0);
+    let threads = if first.is_none() { 1 } else { threads.max(1) };
+
+    std::thread::scope(|scope| {
+        let handles = (0..threads)
+            .map(|stripe| {
+                let init = &init;
+                let visit = &visit;
+                scope.spawn(move || {
+                    let mut acc = init();
+                    let mut hands = known.to_vec();
+                    let mut visit_hands = |hands: &[CardSet]| visit(&mut acc, hands);
+
+                    match first {
+                        None => visit_hands(&hands),
+                        Some(hand) => {
+                            // The hand's first card is its lowest index in
+                            // the deck, so the rest come from after it.
+                            for (index, card) in deck.iter().enumerate().skip(stripe).step_by(threads) {
+                                for rest in deck[index + 1..].iter().combinations(needed[hand] - 1) {
+                                    let drawn = std::iter::once(card).chain(rest).collect::<CardSet>();
+                                    hands[hand] = known[hand] | drawn;
+                                    deal_rest(deck, needed, hand + 1, drawn, &mut hands, &mut visit_hands);
+                                }
+                            }
+                        }
+                    }
+                    acc
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    })
+}
+
+/// State of a search for the lowest deal of each suit symmetry class.
+/// The search is split between threads at the points where `split_hand`
+/// has been dealt `split_depth` cards, each thread taking every
+/// `threads`th of them from `stripe`.
+struct DistinctDeals<'a, V> {
+    needed: &'a [usize],
+    hands: Vec<CardSet>,
+    visit: V,
+    split_hand: usize,
+    split_depth: usize,
+    stripe: usize,
+    threads: usize,
+    splits: usize
+}
+
+impl<'a, V> DistinctDeals<'a, V> where V: FnMut(&[CardSet], usize) {
+    /// Whether this thread deals on from drawing `next` after `drawn`.
+    fn claim(&mut self, hand: usize, drawn: u64, next: u64) -> bool {
+        let depth = self.split_depth as u32;
+        if hand != self.split_hand || drawn.count_ones() >= depth || next.count_ones() < depth {
+            return true;
+        }
+
+        let mine = self.splits % self.threads == self.stripe;
+        self.splits += 1;
+        return mine;
+    }
+
+    /// Deals the rest of hand `hand` on from `level` down, having drawn
+    /// `drawn` so far, then the hands after it. Only deals that no
+    /// permutation in `unchanged`, the symmetries leaving the earlier hands
+    /// alone, maps to a lower deal are visited, along with the number of
+    /// symmetries leaving the whole deal alone. A permutation keeps each
+    /// card's rank, so one that lowers or raises the cards drawn from the
+    /// top levels does the same to every deal completing them.
+    fn deal(&mut self, hand: usize, level: u32, available: u64, drawn: u64, unchanged: &[SuitPermutation]) {
+        if hand == self.needed.len() {
+            (self.visit)(&self.hands, unchanged.len());
+            return;
+        }
+
+        let remaining = self.needed[hand] - drawn.count_ones() as usize;
+        if remaining == 0 {
+            let known = self.hands[hand];
+            self.hands[hand] = known | CardSet::from_bits(drawn);
+            self.deal(hand + 1, TOP_LEVEL, available & !drawn, 0, unchanged);
+            self.hands[hand] = known;
+            return;
+        }
+
+        let shift = 4 * level;
+        let level_cards = (available >> shift) & 0xf;
+        let below = (available & ((1 << shift) - 1)).count_ones() as usize;
+
+        let mut subset = level_cards;
+        loop {
+            let count = subset.count_ones() as usize;
+            if count <= remaining && remaining - count <= below {
+                let next_level = if count == remaining { level } else { level - 1 };
+                let next = drawn | subset << shift;
+                if !self.claim(hand, drawn, next) {
+                    // Another thread deals this part.
+                } else if subset == 0 || unchanged.len() == 1 {
+                    self.deal(hand, next_level, available, next, unchanged);
+                } else if let Some(still_unchanged) = stabilizer(CardSet::from_bits(next), unchanged) {
+                    self.deal(hand, next_level, available, next, &still_unchanged);
+                }
+            }
+
+            if subset == 0 {
+                break;
+            }
+            subset = (subset - 1) & level_cards;
+        }
+    }
+}
+
+/// Like `for_each_deal`, but visits only the lowest of each class of deals
+/// related by one of the suit `symmetries`, comparing the hands in turn by
+/// `CardSet` value, along with the number of deals in its class. The
+/// symmetries must map the deck and each known hand onto itself. A deck
+/// holding more than one joker is dealt in full.
+pub fn for_each_distinct_deal<T, I, V>(deck: &[Card], known: &[CardSet], needed: &[usize], symmetries: &[SuitPermutation], threads: usize, init: I, visit: V) -> Vec<T>
+where T: Send, I: Fn() -> T + Sync, V: Fn(&mut T, &[CardSet], usize) + Sync {
+    let available = deck.iter().collect::<CardSet>();
+    if symmetries.len() <= 1 || available.len() < deck.len() {
+        return for_each_deal(deck, known, needed, threads, init, |acc, hands| visit(acc, hands, 1));
+    }
+
+    // Splitting once the first hand dealt to has two cards gives each
+    // thread plenty of small pieces while the shared walk above them stays
+    // cheap.
+    let split_hand = needed.iter().position(|n| *n > 0).unwrap_or(needed.len());
+    let split_depth = needed.get(split_hand).map_or(0, |n| (*n).min(2));
+    let threads = if split_depth == 0 { 1 } else { threads.max(1) };
+    std::thread::scope(|scope| {
+        let handles = (0..threads)
+            .map(|stripe| {
+                let init = &init;
+                let visit = &visit;
+                scope.spawn(move || {
+                    let mut acc = init();
+                    let mut deals = DistinctDeals {
+                        needed,
+                        hands: known.to_vec(),
+                        visit: |hands: &[CardSet], unchanged: usize| visit(&mut acc, hands, symmetries.len() / unchanged),
+                        split_hand,
+                        split_depth,
+                        stripe,
+                        threads,
+                        splits: 0
+                    };
+                    deals.deal(0, TOP_LEVEL, available.bits(), 0, symmetries);
+                    acc
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Calls `visit` with random deals of `needed[i]` more cards to each hand
+/// `known[i]` until the trial count or time limit in `options` runs out.
+pub fn for_each_random_deal<V>(deck: &mut [Card], known: &[CardSet], needed: &[usize], options: &MonteCarloOptions, mut visit: V)
+where V: FnMut(&[CardSet]) {
+    let total = needed.iter().sum::<usize>();
+    let mut hands = known.to_vec();
+
+    for_each_draw(deck, total, options, |drawn| {
+        let mut rest = drawn;
+        for (hand, n) in needed.iter().enumerate() {
+            let (dealt, remaining) = rest.split_at(*n);
+            hands[hand] = known[hand] | dealt.iter().collect::<CardSet>();
+            rest = remaining;
+        }
+        visit(&hands);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use crate::deck::make_deck_without;
+
+    #[test]
+    fn test_deal_count() {
+        assert_eq!(deal_count(40, &[1, 1]), Some(40 * 39));
+        assert_eq!(deal_count(44, &[2, 0, 1]), Some(946 * 42));
+        assert_eq!(deal_count(3, &[2, 2]), Some(0));
+        assert_eq!(deal_count(52, &[7, 7, 7, 7, 7, 7, 7]), None);
+    }
+
+    #[test]
+    fn test_every_deal_is_visited_once() {
+        let known = vec![CardSet::from(&CardVector::parse("AcAd")), CardSet::from(&CardVector::parse("KcKd"))];
+        let deck = make_deck_without(&(known[0] | known[1]))[..8].to_vec();
+
+        let stripes = for_each_deal(&deck, &known, &[2, 1], 3, Vec::new, |deals, hands| {
+            assert!(hands[0].is_disjoint(&hands[1]));
+            deals.push((hands[0], hands[1]));
+        });
+
+        let mut deals = stripes.into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(deals.len() as u64, deal_count(8, &[2, 1]).unwrap());
+        deals.sort_by_key(|(a, b)| (a.bits(), b.bits()));
+        deals.dedup();
+        assert_eq!(deals.len(), 28 * 6);
+    }
+
+    #[test]
+    fn test_deals_are_split_when_the_first_hand_is_complete() {
+        use crate::suit_symmetry::symmetries;
+
+        let known = vec![CardSet::from(&CardVector::parse("AcAdAhAs")), CardSet::new(), CardSet::new()];
+        let deck = make_deck_without(&known[0]);
+        let count = deal_count(deck.len(), &[0, 2, 1]).unwrap();
+
+        let stripes = for_each_deal(&deck, &known, &[0, 2, 1], 3, || 0u64, |deals, _| *deals += 1);
+        assert!(stripes.iter().all(|deals| *deals > 0));
+        assert_eq!(stripes.iter().sum::<u64>(), count);
+
+        let symmetries = symmetries(&[known[0]]);
+        let stripes = for_each_distinct_deal(&deck, &known, &[0, 2, 1], &symmetries, 3, || 0u64, |deals, _, weight| *deals += weight as u64);
+        assert!(stripes.iter().all(|deals| *deals > 0));
+        assert_eq!(stripes.iter().sum::<u64>(), count);
+    }
+
+    #[test]
+    fn test_distinct_deals_cover_every_deal() {
+        use crate::suit_symmetry::symmetries;
+
+        let aces = CardSet::from(&CardVector::parse("AcAd"));
+        let symmetries = symmetries(&[aces]);
+        let deck = make_deck_without(&aces);
+        let known = vec![CardSet::new(), CardSet::new()];
+        let stripes = for_each_distinct_deal(&deck, &known, &[2, 1], &symmetries, 2, Vec::new, |deals, hands, weight| {
+            assert!(hands[0].is_disjoint(&hands[1]));
+            deals.push((hands[0], hands[1], weight));
+        });
+
+        // Every deal is the image of exactly one visited deal, which is the
+        // lowest in its class
+        let deals = stripes.into_iter().flatten().collect::<Vec<_>>();
+        let mut images = Vec::new();
+        for (first, second, weight) in deals.iter() {
+            let mut class = symmetries.iter()
+                .map(|permutation| (permutation.apply(*first).bits(), permutation.apply(*second).bits()))
+                .collect::<Vec<_>>();
+            class.sort();
+            class.dedup();
+            assert_eq!(class[0], (first.bits(), second.bits()));
+            assert_eq!(class.len(), *weight);
+            images.extend(class);
+        }
+        assert_eq!(images.len() as u64, deal_count(50, &[2, 1]).unwrap());
+        images.sort();
+        images.dedup();
+        assert_eq!(images.len() as u64, deal_count(50, &[2, 1]).unwrap());
+    }
+}
@@ -5,6 +5,7 @@ use rand::Rng;
 use super::card::Suit;
 use super::card::Rank;
 use super::card::Card;
+use super::card::CardSet;
 
 pub fn make_deck_with_jokers(n: usize) -> Vec<Card> {
     let mut deck = Vec::with_capacity(52);
@@ -30,7 +31,14 @@ pub fn make_deck() -> Vec<Card> {
     make_deck_with_jokers(0)
 }
 
-pub fn shuffle(deck: &mut Vec<Card>) {
+/// The 36 card short deck used by six plus hold'em, without deuces through fives.
+pub fn make_short_deck() -> Vec<Card> {
+    let mut deck = make_deck();
+    deck.retain(|card| card.rank >= Rank::Six);
+    return deck;
+}
+
+pub fn shuffle(deck: &mut [Card]) {
     let mut rng = rand::thread_rng();
     let n = deck.len();
     for i in 0 .. n - 2 {
@@ -39,7 +47,7 @@ pub fn shuffle(deck: &mut Vec<Card>) {
     }
 }
 
-pub fn shuffle_deck(deck: &Vec<Card>) -> Vec<Card> {
+pub fn shuffle_deck(deck: &[Card]) -> Vec<Card> {
     let mut copy = deck.to_vec();
     shuffle(&mut copy);
     return copy;
@@ -49,6 +57,28 @@ pub fn make_shuffled_deck() -> Vec<Card> {
     shuffle_deck(&make_deck())
 }
 
+pub fn remove_card(deck: &mut Vec<Card>, card: &Card) {
+    if let Some(pos) = deck.iter().position(|x| *x == *card) {
+        deck.remove(pos);
+    }
+}
+
+pub fn remove_all_cards(deck: &mut Vec<Card>, cards: &[Card]) {
+    let dead = CardSet::from(cards);
+    deck.retain(|card| Card::is_joker(card) || !dead.contains(card));
+
+    // Jokers share a single bit, so remove them one at a time
+    for joker in cards.iter().filter(|card| Card::is_joker(card)) {
+        remove_card(deck, joker);
+    }
+}
+
+pub fn make_deck_without(dead: &CardSet) -> Vec<Card> {
+    let mut deck = make_deck();
+    deck.retain(|card| !dead.contains(card));
+    return deck;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +112,30 @@ mod tests {
         let deck = make_deck_with_jokers(2);
         assert_eq!(deck.len(), 54);
     }
+
+    #[test]
+    fn test_remove_all_cards_with_jokers() {
+        let mut deck = make_deck_with_jokers(2);
+        remove_all_cards(&mut deck, &[Ace.of(Clubs), Rank::Joker.of(Suit::Joker)]);
+        assert_eq!(deck.len(), 52);
+        assert!(deck.contains(&Rank::Joker.of(Suit::Joker)));
+    }
+
+    #[test]
+    fn test_make_deck_without() {
+        let dead = CardSet::from(&[Ace.of(Clubs), King.of(Hearts)][..]);
+        let deck = make_deck_without(&dead);
+        assert_eq!(deck.len(), 50);
+        assert!(!deck.contains(&Ace.of(Clubs)));
+        assert!(!deck.contains(&King.of(Hearts)));
+    }
+
+    #[test]
+    fn test_remove_all_cards() {
+        let mut deck = make_deck();
+        remove_all_cards(&mut deck, &[Ace.of(Clubs), King.of(Hearts)]);
+        assert_eq!(deck.len(), 50);
+        assert!(!deck.contains(&Ace.of(Clubs)));
+        assert!(!deck.contains(&King.of(Hearts)));
+    }
 }
@@ -0,0 +1,160 @@
+//! Discard advice for five card draw: tries every way of holding cards from
+//! a five card hand and reports how each hold plays out against an opponent.
+
+use itertools::Itertools;
+
+use super::card::{Card, CardSet, fmt_cards};
+use super::deck::{make_deck, remove_all_cards};
+use super::fast_eval;
+use super::win_lose_split::WinLoseSplit;
+use super::hand_rank_count::HandRankCount;
+use super::hold_em::record_winners;
+use super::monte_carlo::MonteCarloOptions;
+use super::deal::{deal_count, for_each_deal, for_each_random_deal};
+use super::draw::{DrawGame, DrawHand, find_draw_winners};
+
+/// Holds with at most this many outcomes are enumerated, the rest sampled.
+pub const MAX_HOLD_DEALS: u64 = 1_000_000;
+
+/// How one hold plays out: the final hand ranks it makes and its results
+/// against the opponent.
+pub struct HoldResult {
+    pub kept: Vec<Card>,
+    pub discarded: Vec<Card>,
+    pub hand_rank_count: HandRankCount,
+    pub results: WinLoseSplit
+}
+
+impl HoldResult {
+    /// Fraction of the pot won, counting splits as half.
+    pub fn equity(&self) -> f64 {
+        (self.results.wins as f64 + self.results.splits as f64 / 2.0) / (self.results.total() as f64)
+    }
+}
+
+impl std::fmt::Display for HoldResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kept = if self.kept.is_empty() { "nothing".to_string() } else { fmt_cards(&self.kept) };
+        write!(f, "hold {} draw {} - {:.2}% equity - {}",
+               kept, self.discarded.len(), 100.0 * self.equity(), self.results)
+    }
+}
+
+fn award_hold(hands: &[CardSet], results: &mut [WinLoseSplit], hand_rank_count: &mut HandRankCount) {
+    let game = DrawGame::FiveCardDraw;
+    hand_rank_count.inc(fast_eval::hand_rank(game.score(hands[0])));
+    record_winners(results, &find_draw_winners(game, hands));
+}
+
+/// Plays out holding `kept` and drawing to replace `discarded`. The discards
+/// and `dead` cards are never drawn.
+pub fn evaluate_hold(kept: &[Card], discarded: &[Card], opponent: &DrawHand, dead: &[Card], options: &MonteCarloOptions, threads: usize) -> HoldResult {
+    let game = DrawGame::FiveCardDraw;
+
+    let mut known = kept.to_vec();
+    known.extend_from_slice(discarded);
+    known.extend_from_slice(&opponent.kept);
+    known.extend_from_slice(dead);
+    let mut deck = make_deck();
+    remove_all_cards(&mut deck, &known);
+
+    let hands = vec![CardSet::from(kept), CardSet::from(opponent.kept.as_slice())];
+    let needed = vec![discarded.len(), opponent.draw_count(game)];
+
+    let mut results = vec![WinLoseSplit::new(); 2];
+    let mut hand_rank_count = HandRankCount::new();
+
+    if deal_count(deck.len(), &needed).is_some_and(|count| count <= MAX_HOLD_DEALS) {
+        let stripes = for_each_deal(&deck, &hands, &needed, threads,
+            || (vec![WinLoseSplit::new(); 2], HandRankCount::new()),
+            |(results, counts), complete_hands| award_hold(complete_hands, results, counts));
+
+        for (stripe_results, stripe_counts) in stripes {
+            for (result, stripe_result) in results.iter_mut().zip(stripe_results) {
+                *result += stripe_result;
+            }
+            hand_rank_count += &stripe_counts;
+        }
+    } else {
+        for_each_random_deal(&mut deck, &hands, &needed, options, |complete_hands| {
+            award_hold(complete_hands, &mut results, &mut hand_rank_count);
+        });
+    }
+
+    HoldResult {
+        kept: kept.to_vec(),
+        discarded: discarded.to_vec(),
+        hand_rank_count: hand_rank_count,
+        results: results[0]
+    }
+}
+
+/// Evaluates every hold of a five card `hand` against `opponent`, best
+/// equity first. Holds with equal equity keep the order in which they were
+/// tried, from holding all five cards down to drawing five.
+pub fn advise_discard(hand: &[Card], opponent: &DrawHand, dead: &[Card], options: &MonteCarloOptions, threads: usize) -> Vec<HoldResult> {
+    let mut holds = Vec::new();
+    for size in (0..=hand.len()).rev() {
+        for kept in hand.iter().cloned().combinations(size) {
+            let kept_set = CardSet::from(kept.as_slice());
+            let discarded = hand.iter()
+                .filter(|card| !kept_set.contains(card))
+                .cloned()
+                .collect::<Vec<_>>();
+            holds.push(evaluate_hold(&kept, &discarded, opponent, dead, options, threads));
+        }
+    }
+
+    holds.sort_by(|a, b| b.equity().total_cmp(&a.equity()));
+    return holds;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use crate::poker_hand::HandRank;
+
+    fn advise(hand: &str, opponent: &str) -> Vec<HoldResult> {
+        let hand = CardVector::parse(hand).to_vec();
+        let opponent = opponent.parse::<DrawHand>().unwrap();
+        advise_discard(&hand, &opponent, &[], &MonteCarloOptions::new(2000).with_seed(5), 2)
+    }
+
+    #[test]
+    fn test_drawing_to_a_royal_flush() {
+        let holds = advise("Ah Kh Qh Jh 2c", "9c 9d 9s 5c 5d/0");
+        assert_eq!(holds.len(), 32);
+        assert_eq!(holds.iter().filter(|hold| hold.kept.len() == 3).count(), 10);
+
+        // Only the Th beats the pat full house
+        let best = &holds[0];
+        assert_eq!(best.kept, CardVector::parse("Ah Kh Qh Jh").to_vec());
+        assert_eq!(best.results.total(), 42);
+        assert_eq!(best.results.wins, 1);
+
+        let counts = &best.hand_rank_count;
+        assert_eq!(counts[HandRank::StraightFlush as usize], 1);
+        assert_eq!(counts[HandRank::Flush as usize], 8);
+        assert_eq!(counts[HandRank::Straight as usize], 3);
+        assert_eq!(counts[HandRank::OnePair as usize], 12);
+        assert_eq!(counts[HandRank::HighCard as usize], 18);
+    }
+
+    #[test]
+    fn test_standing_pat() {
+        let holds = advise("9h 9c 9d 5h 5s", "Ac Ad/3");
+        assert_eq!(holds[0].kept.len(), 5);
+        assert!(holds[0].equity() > 0.9);
+    }
+
+    #[test]
+    fn test_random_opponent_is_sampled() {
+        let hand = CardVector::parse("Ac Ad 7h 4s 2c").to_vec();
+        let opponent = "/5".parse::<DrawHand>().unwrap();
+        let hold = evaluate_hold(&hand[..2], &hand[2..], &opponent, &[], &MonteCarloOptions::new(500).with_seed(9), 1);
+        assert_eq!(hold.results.total(), 500);
+        assert_eq!(hold.hand_rank_count.iter().sum::<usize>(), 500);
+        assert_eq!(hold.hand_rank_count[HandRank::HighCard as usize], 0);
+    }
+}
@@ -0,0 +1,351 @@
+//! Equity for draw games, where each player keeps some cards and draws the
+//! rest of their hand from the deck. Cards known to be out of play, such as
+//! discards, are passed as `dead` and never drawn.
+//!
+//! Equity is for the next draw followed by a showdown, so in triple draw it
+//! treats the next draw as the last one.
+
+use strum::IntoEnumIterator;
+
+use super::card::{Card, CardSet, CardVector, fmt_cards};
+use super::deck::{make_deck, remove_all_cards};
+use super::fast_eval;
+use super::poker_hand::{PokerHand, BadugiHand};
+use super::low_hand::LowHand;
+use super::win_lose_split::WinLoseSplit;
+use super::hold_em::record_winners;
+use super::monte_carlo::MonteCarloOptions;
+use super::deal::{deal_count, for_each_deal, for_each_random_deal};
+
+type ParseError = &'static str;
+
+pub const BADUGI_HAND_SIZE: usize = 4;
+
+/// Draw games supported by the equity calculators.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, EnumIter, Display)]
+pub enum DrawGame {
+    /// Five card draw, high hand wins
+    #[strum(to_string = "draw")]
+    FiveCardDraw,
+    /// Deuce-to-seven lowball, usually played as triple draw
+    #[strum(to_string = "2-7")]
+    DeuceToSeven,
+    #[strum(to_string = "badugi")]
+    Badugi,
+}
+
+impl DrawGame {
+    pub fn hand_size(&self) -> usize {
+        match self {
+            DrawGame::FiveCardDraw | DrawGame::DeuceToSeven => 5,
+            DrawGame::Badugi => BADUGI_HAND_SIZE
+        }
+    }
+
+    /// Scores a complete hand so that a higher score always wins, whether
+    /// the game is played high or low.
+    pub fn score(&self, cards: CardSet) -> i32 {
+        match self {
+            DrawGame::FiveCardDraw => {
+                if fast_eval::can_evaluate(&cards) {
+                    return fast_eval::evaluate(cards);
+                }
+                PokerHand::build(&cards.to_vec().iter().collect::<Vec<_>>(), &None).score
+            },
+            DrawGame::DeuceToSeven => -LowHand::deuce_to_seven(&cards.to_vec().iter().collect::<Vec<_>>()).score,
+            DrawGame::Badugi => -BadugiHand::build(&cards.to_vec().iter().collect::<Vec<_>>()).score
+        }
+    }
+}
+
+impl std::str::FromStr for DrawGame {
+    type Err = ParseError;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let lower_case = str.to_ascii_lowercase();
+        for game in Self::iter() {
+            if lower_case == game.to_string() {
+                return Ok(game);
+            }
+        }
+        return Err("Invalid Draw Game");
+    }
+}
+
+/// The cards a player keeps and how many they draw. Parsed from `kept/n`,
+/// e.g. `7c5d4h/2`; without `/n` the player draws back to a full hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawHand {
+    pub kept: Vec<Card>,
+    pub draws: Option<usize>
+}
+
+impl DrawHand {
+    pub fn new(kept: Vec<Card>, draws: usize) -> DrawHand {
+        DrawHand {
+            kept: kept,
+            draws: Some(draws)
+        }
+    }
+
+    /// Keeps `kept` and draws back to a full hand.
+    pub fn keeping(kept: Vec<Card>) -> DrawHand {
+        DrawHand {
+            kept: kept,
+            draws: None
+        }
+    }
+
+    /// Number of cards drawn in `game`.
+    pub fn draw_count(&self, game: DrawGame) -> usize {
+        self.draws.unwrap_or_else(|| game.hand_size().saturating_sub(self.kept.len()))
+    }
+}
+
+impl std::str::FromStr for DrawHand {
+    type Err = ParseError;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str.split_once('/') {
+            Some((kept, draws)) => {
+                let draws = draws.trim().parse::<usize>().map_err(|_| "Invalid draw count")?;
+                Ok(DrawHand::new(kept.parse::<CardVector>()?.0, draws))
+            },
+            None => Ok(DrawHand::keeping(str.parse::<CardVector>()?.0))
+        }
+    }
+}
+
+impl std::fmt::Display for DrawHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.draws {
+            Some(draws) if self.kept.is_empty() => write!(f, "draws {}", draws),
+            Some(draws) => write!(f, "{} draws {}", fmt_cards(&self.kept), draws),
+            None => write!(f, "{}", fmt_cards(&self.kept))
+        }
+    }
+}
+
+/// Checks that every hand draws back to exactly a full hand, that no card
+/// appears twice and that the deck holds enough cards for every draw.
+pub fn validate_draw_hands(game: DrawGame, hands: &[DrawHand], dead: &[Card]) -> Result<(), ParseError> {
+    for hand in hands {
+        if hand.kept.len() + hand.draw_count(game) != game.hand_size() {
+            return Err("Kept and drawn cards must make a full hand");
+        }
+    }
+
+    let known = known_cards(hands, dead);
+    if known.iter().collect::<CardSet>().len() != known.len() {
+        return Err("The same card appears more than once");
+    }
+
+    let needed = draw_counts(game, hands).iter().sum::<usize>();
+    if needed > 52 - known.len() {
+        return Err("Not enough cards left in the deck for every draw");
+    }
+
+    return Ok(());
+}
+
+fn known_cards(hands: &[DrawHand], dead: &[Card]) -> Vec<Card> {
+    hands.iter()
+        .flat_map(|hand| hand.kept.iter())
+        .chain(dead.iter())
+        .cloned()
+        .collect()
+}
+
+fn make_draw_deck(hands: &[DrawHand], dead: &[Card]) -> Vec<Card> {
+    let mut deck = make_deck();
+    remove_all_cards(&mut deck, &known_cards(hands, dead));
+    return deck;
+}
+
+fn kept_sets(hands: &[DrawHand]) -> Vec<CardSet> {
+    hands.iter()
+        .map(|hand| CardSet::from(hand.kept.as_slice()))
+        .collect()
+}
+
+fn draw_counts(game: DrawGame, hands: &[DrawHand]) -> Vec<usize> {
+    hands.iter()
+        .map(|hand| hand.draw_count(game))
+        .collect()
+}
+
+/// Players holding the best hand after the draw.
+pub fn find_draw_winners(game: DrawGame, hands: &[CardSet]) -> Vec<usize> {
+    let mut vec = Vec::new();
+
+    let mut best_score = None;
+    for (index, cards) in hands.iter().enumerate() {
+        let score = game.score(*cards);
+        if best_score.is_none_or(|max| score > max) {
+            vec.clear();
+            best_score = Some(score);
+        }
+        if best_score == Some(score) {
+            vec.push(index);
+        }
+    }
+
+    return vec;
+}
+
+/// Number of distinct outcomes of the draw, or `None` when it does not fit
+/// in a `u64`.
+pub fn draw_deal_count(game: DrawGame, hands: &[DrawHand], dead: &[Card]) -> Option<u64> {
+    return deal_count(make_draw_deck(hands, dead).len(), &draw_counts(game, hands));
+}
+
+/// Exhaustive equity for the next draw.
+pub fn draw_odds(game: DrawGame, hands: &[DrawHand], dead: &[Card], threads: usize) -> Vec<WinLoseSplit> {
+    let deck = make_draw_deck(hands, dead);
+
+    let stripes = for_each_deal(&deck, &kept_sets(hands), &draw_counts(game, hands), threads,
+        || vec![WinLoseSplit::new(); hands.len()],
+        |results, complete_hands| record_winners(results, &find_draw_winners(game, complete_hands)));
+
+    let mut results = vec![WinLoseSplit::new(); hands.len()];
+    for stripe_results in stripes {
+        for (result, stripe_result) in results.iter_mut().zip(stripe_results) {
+            *result += stripe_result;
+        }
+    }
+    return results;
+}
+
+/// Sampled equity for the next draw.
+pub fn draw_monte_carlo(game: DrawGame, hands: &[DrawHand], dead: &[Card], options: &MonteCarloOptions) -> Vec<WinLoseSplit> {
+    let mut deck = make_draw_deck(hands, dead);
+    let mut results = vec![WinLoseSplit::new(); hands.len()];
+
+    for_each_random_deal(&mut deck, &kept_sets(hands), &draw_counts(game, hands), options, |complete_hands| {
+        record_winners(&mut results, &find_draw_winners(game, complete_hands));
+    });
+
+    return results;
+}
+
+fn keeping(kept: &[Vec<Card>]) -> Vec<DrawHand> {
+    kept.iter()
+        .map(|cards| DrawHand::keeping(cards.to_vec()))
+        .collect()
+}
+
+/// Number of distinct Badugi draws, or `None` when it does not fit in a `u64`.
+pub fn badugi_deal_count(kept: &[Vec<Card>], dead: &[Card]) -> Option<u64> {
+    draw_deal_count(DrawGame::Badugi, &keeping(kept), dead)
+}
+
+/// Exhaustive Badugi equity for one draw, with each player drawing back to
+/// four cards from the cards they keep.
+pub fn badugi_odds(kept: &[Vec<Card>], dead: &[Card], threads: usize) -> Vec<WinLoseSplit> {
+    draw_odds(DrawGame::Badugi, &keeping(kept), dead, threads)
+}
+
+/// Sampled Badugi equity for one draw.
+pub fn badugi_monte_carlo(kept: &[Vec<Card>], dead: &[Card], options: &MonteCarloOptions) -> Vec<WinLoseSplit> {
+    draw_monte_carlo(DrawGame::Badugi, &keeping(kept), dead, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(kept: &[&str]) -> Vec<Vec<Card>> {
+        kept.iter()
+            .map(|cards| CardVector::parse(cards).to_vec())
+            .collect()
+    }
+
+    fn hands(strs: &[&str]) -> Vec<DrawHand> {
+        strs.iter().map(|str| str.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_pat_hands() {
+        let results = badugi_odds(&parse(&["Ac 2d 3h 4s", "As 2h 3d 5c"]), &[], 1);
+        assert_eq!(results[0].total(), 1);
+        assert_eq!(results[0].wins, 1);
+    }
+
+    #[test]
+    fn test_one_card_draw() {
+        let kept = parse(&["Ac 2d 3h", "Kc Qd Jh Ts"]);
+        let dead = CardVector::parse("5c 5d");
+        assert_eq!(badugi_deal_count(&kept, &dead), Some(43));
+
+        // The nine spades from the 4s up, other than the Ts, all make a winning badugi
+        let results = badugi_odds(&kept, &dead, 1);
+        assert_eq!(results[0].total(), 43);
+        assert_eq!(results[0].wins, 9);
+        assert_eq!(results[1].wins, 34);
+    }
+
+    #[test]
+    fn test_threaded_matches_single_thread() {
+        let kept = parse(&["Ac 2d", "3h 4s 5c"]);
+        let single = badugi_odds(&kept, &[], 1);
+        let threaded = badugi_odds(&kept, &[], 4);
+        for i in 0..kept.len() {
+            assert_eq!(single[i].wins, threaded[i].wins);
+            assert_eq!(single[i].splits, threaded[i].splits);
+            assert_eq!(single[i].losses, threaded[i].losses);
+        }
+    }
+
+    #[test]
+    fn test_monte_carlo_converges_to_enumeration() {
+        let kept = parse(&["Ac 2d", "3h 4s 5c"]);
+        let exact = badugi_odds(&kept, &[], 1);
+
+        let sampled = badugi_monte_carlo(&kept, &[], &MonteCarloOptions::new(3000).with_seed(3));
+        assert_eq!(sampled[0].total(), 3000);
+        let (low, high) = sampled[0].win_confidence_interval(4.0);
+        assert!(low <= exact[0].win_pct() && exact[0].win_pct() <= high);
+    }
+
+    #[test]
+    fn test_parse_draw_hand() {
+        let hand = "7c 5d 4h/2".parse::<DrawHand>().unwrap();
+        assert_eq!(hand.kept, CardVector::parse("7c5d4h").to_vec());
+        assert_eq!(hand.draw_count(DrawGame::DeuceToSeven), 2);
+        assert_eq!("7c 5d 4h".parse::<DrawHand>().unwrap().draw_count(DrawGame::Badugi), 1);
+        assert!("7c 5d 4h/x".parse::<DrawHand>().is_err());
+        assert!("7c 5z 4h/2".parse::<DrawHand>().is_err());
+        assert!("7c 5d Xh".parse::<DrawHand>().is_err());
+        assert_eq!("2-7".parse::<DrawGame>(), Ok(DrawGame::DeuceToSeven));
+    }
+
+    #[test]
+    fn test_validate_draw_hands() {
+        let game = DrawGame::FiveCardDraw;
+        assert!(validate_draw_hands(game, &hands(&["AcAd/3", "KcKdKh/2"]), &[]).is_ok());
+        assert!(validate_draw_hands(game, &hands(&["AcAd/2", "KcKdKh/2"]), &[]).is_err());
+        assert!(validate_draw_hands(game, &hands(&["AcAd/3", "AcKdKh/2"]), &[]).is_err());
+    }
+
+    #[test]
+    fn test_five_card_draw() {
+        // Trips drawing two against a pat flush: only a full house or quads wins
+        let game = DrawGame::FiveCardDraw;
+        let results = draw_odds(game, &hands(&["7c 7d 7h/2", "Ks Js 9s 6s 3s"]), &[], 1);
+        assert_eq!(results[0].total() as u64, draw_deal_count(game, &hands(&["7c 7d 7h", "Ks Js 9s 6s 3s"]), &[]).unwrap());
+        assert_eq!(results[0].total(), 44 * 43 / 2);
+
+        // Quads with the 7s and any other card, or a full house from a pair of the
+        // seven ranks with four cards left or the five with three left
+        assert_eq!(results[0].wins, 43 + 7 * 6 + 5 * 3);
+        assert_eq!(results[0].splits, 0);
+    }
+
+    #[test]
+    fn test_deuce_to_seven_draw() {
+        let game = DrawGame::DeuceToSeven;
+        let results = draw_odds(game, &hands(&["7c 5d 4h 3s", "8c 6d 4s 3c 2d"]), &[], 1);
+        assert_eq!(results[0].total(), 43);
+
+        // Only the three remaining deuces win: a six makes a straight and an eight loses to 8-6
+        assert_eq!(results[0].wins, 3);
+    }
+}
@@ -0,0 +1,368 @@
+//! Allocation-free evaluator for 5, 6 or 7 natural cards.
+//!
+//! Hands are passed as a `CardSet` and scored with the same encoding as
+//! `PokerHand::score`: the `HandRank` followed by one nibble per card of the
+//! best five, so values from either evaluator can be compared directly.
+//! Ranks are counted with bit masks; only straight detection uses a
+//! precomputed table.
+
+use super::card::{Suit, Rank, Card, CardSet};
+use super::poker_hand::HandRank;
+
+const RANKS: usize = 13;
+const ACE: u32 = 14;
+
+const fn straight_high(mask: usize) -> u8 {
+    let mut high = RANKS - 1;
+    while high >= 4 {
+        let run = 0b11111 << (high - 4);
+        if mask & run == run {
+            return (high + 2) as u8;
+        }
+        high -= 1;
+    }
+
+    let wheel = (1 << (RANKS - 1)) | 0b1111;
+    if mask & wheel == wheel {
+        return 5;
+    }
+
+    return 0;
+}
+
+const fn make_straight_table() -> [u8; 1 << RANKS] {
+    let mut table = [0; 1 << RANKS];
+    let mut mask = 0;
+    while mask < 1 << RANKS {
+        table[mask] = straight_high(mask);
+        mask += 1;
+    }
+    return table;
+}
+
+/// Rank of the highest straight contained in a 13-bit rank mask (deuce in
+/// bit 0), or zero if there is none.
+static STRAIGHTS: [u8; 1 << RANKS] = make_straight_table();
+
+fn push(score: &mut i32, n: &mut usize, rank: u32) {
+    *score = *score * 16 + rank as i32;
+    *n += 1;
+}
+
+fn score_straight(hand_rank: HandRank, high: u32) -> i32 {
+    let mut score = hand_rank as i32;
+    let mut n = 0;
+    if high == 5 {
+        for rank in [5, 4, 3, 2, ACE].iter() {
+            push(&mut score, &mut n, *rank);
+        }
+    } else {
+        for rank in (high - 4 ..= high).rev() {
+            push(&mut score, &mut n, rank);
+        }
+    }
+    return score;
+}
+
+fn score_top_five(hand_rank: HandRank, mask: u16) -> i32 {
+    let mut score = hand_rank as i32;
+    let mut n = 0;
+    for r in (0..RANKS).rev() {
+        if n >= 5 {
+            break;
+        }
+        if mask & (1 << r) != 0 {
+            push(&mut score, &mut n, r as u32 + 2);
+        }
+    }
+    return score;
+}
+
+fn score_sets(hand_rank: HandRank, counts: &[u8; RANKS], sizes: &[u8]) -> Option<i32> {
+    let mut used = 0u16;
+    let mut score = hand_rank as i32;
+    let mut n = 0;
+
+    for size in sizes {
+        let rank = (0..RANKS).rev()
+            .find(|r| used & (1 << r) == 0 && counts[*r] >= *size)?;
+        used |= 1 << rank;
+        for _i in 0..*size {
+            push(&mut score, &mut n, rank as u32 + 2);
+        }
+    }
+
+    return Some(score);
+}
+
+/// Rank counts, suit masks and the overall rank mask of a set of cards.
+struct Tally {
+    counts: [u8; RANKS],
+    suits: [u16; 4],
+    ranks: u16
+}
+
+fn tally(cards: CardSet) -> Tally {
+    let mut tally = Tally {
+        counts: [0; RANKS],
+        suits: [0; 4],
+        ranks: 0
+    };
+
+    let mut bits = cards.bits();
+    while bits != 0 {
+        let index = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+
+        let rank = index / 4;
+        tally.counts[rank] += 1;
+        tally.suits[index % 4] |= 1 << rank;
+        tally.ranks |= 1 << rank;
+    }
+
+    return tally;
+}
+
+/// Ace, six, seven, eight and nine: the lowest straight in a short deck.
+const SHORT_DECK_WHEEL: u16 = (1 << (RANKS - 1)) | (0b1111 << 4);
+
+fn find_straight(mask: u16, short_deck: bool) -> u32 {
+    let high = STRAIGHTS[mask as usize] as u32;
+    if short_deck && high <= 5 && mask & SHORT_DECK_WHEEL == SHORT_DECK_WHEEL {
+        return 9;
+    }
+    return high;
+}
+
+fn score_any_straight(hand_rank: HandRank, high: u32, short_deck: bool) -> i32 {
+    if short_deck && high == 9 {
+        let mut score = hand_rank as i32;
+        let mut n = 0;
+        for rank in [9, 8, 7, 6, ACE].iter() {
+            push(&mut score, &mut n, *rank);
+        }
+        return score;
+    }
+    return score_straight(hand_rank, high);
+}
+
+/// Scores the best hand of one category, or `None` if the cards do not make it.
+fn score_category(hand_rank: HandRank, tally: &Tally, short_deck: bool) -> Option<i32> {
+    match hand_rank {
+        HandRank::StraightFlush => tally.suits.iter()
+            .filter(|suited| suited.count_ones() >= 5)
+            .map(|suited| find_straight(*suited, short_deck))
+            .find(|high| *high > 0)
+            .map(|high| score_any_straight(hand_rank, high, short_deck)),
+        HandRank::Quads => score_sets(hand_rank, &tally.counts, &[4, 1]),
+        HandRank::FullHouse => score_sets(hand_rank, &tally.counts, &[3, 2]),
+        HandRank::Flush => tally.suits.iter()
+            .find(|suited| suited.count_ones() >= 5)
+            .map(|suited| score_top_five(hand_rank, *suited)),
+        HandRank::Straight => Some(find_straight(tally.ranks, short_deck))
+            .filter(|high| *high > 0)
+            .map(|high| score_any_straight(hand_rank, high, short_deck)),
+        HandRank::Triplets => score_sets(hand_rank, &tally.counts, &[3, 1, 1]),
+        HandRank::TwoPair => score_sets(hand_rank, &tally.counts, &[2, 2, 1]),
+        HandRank::OnePair => score_sets(hand_rank, &tally.counts, &[2, 1, 1, 1]),
+        HandRank::HighCard => Some(score_top_five(hand_rank, tally.ranks)),
+        HandRank::FiveOfAKind => None
+    }
+}
+
+const STANDARD_ORDER: [HandRank; 9] = [
+    HandRank::StraightFlush, HandRank::Quads, HandRank::FullHouse, HandRank::Flush, HandRank::Straight,
+    HandRank::Triplets, HandRank::TwoPair, HandRank::OnePair, HandRank::HighCard
+];
+
+/// Scores the best five-card hand in `cards`, which must hold between five
+/// and seven cards and no jokers.
+pub fn evaluate(cards: CardSet) -> i32 {
+    debug_assert!(cards.len() >= 5 && cards.len() <= 7);
+
+    let tally = tally(cards);
+    return STANDARD_ORDER.iter()
+        .find_map(|hand_rank| score_category(*hand_rank, &tally, false))
+        .expect("Every hand has a high card");
+}
+
+/// Short deck (six plus) hand ranking from best to worst. Flushes beat full
+/// houses, and with `trips_beat_straights` three of a kind beats a straight.
+pub fn short_deck_order(trips_beat_straights: bool) -> [HandRank; 9] {
+    let mut order = [
+        HandRank::StraightFlush, HandRank::Quads, HandRank::Flush, HandRank::FullHouse, HandRank::Straight,
+        HandRank::Triplets, HandRank::TwoPair, HandRank::OnePair, HandRank::HighCard
+    ];
+    if trips_beat_straights {
+        order.swap(4, 5);
+    }
+    return order;
+}
+
+/// Scores the best short deck hand in five to seven cards, where A-6-7-8-9
+/// is the lowest straight. Scores keep the nibble encoding of `evaluate`
+/// below the category, which is replaced by its strength in `order`, so
+/// they only compare with other scores for the same order.
+pub fn evaluate_short_deck(cards: CardSet, order: &[HandRank; 9]) -> (HandRank, i32) {
+    debug_assert!(cards.len() >= 5 && cards.len() <= 7);
+
+    let tally = tally(cards);
+    for (position, hand_rank) in order.iter().enumerate() {
+        if let Some(score) = score_category(*hand_rank, &tally, true) {
+            let strength = (order.len() - 1 - position) as i32;
+            return (*hand_rank, (strength << 20) | (score & 0xFFFFF));
+        }
+    }
+    unreachable!("Every hand has a high card");
+}
+
+/// Bit for a card's ace-to-five low rank (ace in bit 1, king in bit 13), or
+/// zero for jokers and cards higher than `qualifier`.
+pub fn low_bit(card: &Card, qualifier: Rank) -> u16 {
+    match card.rank {
+        Rank::Ace => 1 << 1,
+        Rank::Joker => 0,
+        rank if rank <= qualifier => 1 << rank as usize,
+        _ => 0
+    }
+}
+
+/// Scores exactly five distinct low ranks from `low_bit` with the encoding
+/// of `LowHand::score`.
+pub fn score_low_mask(mask: u16) -> i32 {
+    (1..=13).rev()
+        .filter(|rank| mask & (1 << rank) != 0)
+        .fold(HandRank::HighCard as i32, |acc, rank| acc * 16 + rank)
+}
+
+/// Scores the best eight-or-better ace-to-five low among any number of
+/// cards, or `None` if there is no qualifying low.
+pub fn evaluate_low_eight(cards: CardSet) -> Option<i32> {
+    let mut mask = cards.iter()
+        .fold(0, |acc, card| acc | low_bit(&card, Rank::Eight));
+
+    if mask.count_ones() < 5 {
+        return None;
+    }
+
+    while mask.count_ones() > 5 {
+        mask &= !(1 << (15 - mask.leading_zeros()));
+    }
+
+    return Some(score_low_mask(mask));
+}
+
+/// Extracts the `HandRank` from a score produced by `evaluate`.
+pub fn hand_rank(score: i32) -> HandRank {
+    HandRank::for_ordinal((score >> 20) as usize)
+}
+
+/// True when `evaluate` can score these cards in place of `PokerHand::build`.
+pub fn can_evaluate(cards: &CardSet) -> bool {
+    cards.len() >= 5
+        && cards.len() <= 7
+        && !cards.contains(&Card::new(Rank::Joker, Suit::Joker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use crate::card::CardVector;
+    use crate::deck::make_deck;
+    use crate::poker_hand::PokerHand;
+
+    fn evaluate_str(cards: &str) -> i32 {
+        evaluate(CardSet::from(&CardVector::parse(cards)))
+    }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(hand_rank(evaluate_str("Ac Kc Qc Tc Jc")), HandRank::StraightFlush);
+        assert_eq!(hand_rank(evaluate_str("Ac As Ad Ah Jd")), HandRank::Quads);
+        assert_eq!(hand_rank(evaluate_str("Ac As Ad Jh Jd")), HandRank::FullHouse);
+        assert_eq!(hand_rank(evaluate_str("Ac Kc 7c Tc Jc")), HandRank::Flush);
+        assert_eq!(hand_rank(evaluate_str("Ac 5c 4s 3s 2d")), HandRank::Straight);
+        assert_eq!(hand_rank(evaluate_str("Ac Ah As Ts Jd")), HandRank::Triplets);
+        assert_eq!(hand_rank(evaluate_str("Ac Ah Qs Qd Jd")), HandRank::TwoPair);
+        assert_eq!(hand_rank(evaluate_str("Ac Ah Qs Td Jd")), HandRank::OnePair);
+        assert_eq!(hand_rank(evaluate_str("Ac Jh 9s 7d 5d")), HandRank::HighCard);
+    }
+
+    #[test]
+    fn test_seven_cards() {
+        assert_eq!(hand_rank(evaluate_str("K♣ K♦ 5♠ 5♣ 3♥ 3♣ 2d")), HandRank::TwoPair);
+        assert_eq!(hand_rank(evaluate_str("As Ks 9s 5s 4s 3s 2s")), HandRank::StraightFlush);
+        assert_eq!(hand_rank(evaluate_str("Ac Ad Ah Kc Kd Ks 2s")), HandRank::FullHouse);
+        assert!(evaluate_str("6s 5d 4h 3c 2c Ad Kc") > evaluate_str("5d 4h 3c 2c Ad Kc Qc"));
+    }
+
+    fn assert_matches_poker_hand(cards: &[&Card]) {
+        let expected = PokerHand::build(cards, &None).score;
+        let set = cards.iter().cloned().collect::<CardSet>();
+        assert_eq!(evaluate(set), expected, "{}", crate::card::fmt_cards_refs(cards));
+    }
+
+    #[test]
+    fn test_all_five_card_hands_match_poker_hand() {
+        let deck = make_deck();
+        for hand in deck.iter().combinations(5) {
+            assert_matches_poker_hand(&hand);
+        }
+    }
+
+    #[test]
+    fn test_random_seven_card_hands_match_poker_hand() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut deck = make_deck();
+        for n in (5..=7).cycle().take(30000) {
+            let (cards, _rest) = deck.partial_shuffle(&mut rng, n);
+            assert_matches_poker_hand(&cards.iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_low_eight_matches_low_hand() {
+        use crate::low_hand::LowHand;
+
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut deck = make_deck();
+        for _i in 0..5000 {
+            let (cards, _rest) = deck.partial_shuffle(&mut rng, 7);
+            let expected = LowHand::ace_to_five_qualified(&cards.iter().collect::<Vec<_>>(), Rank::Eight);
+            assert_eq!(evaluate_low_eight(cards.iter().collect()), expected.map(|hand| hand.score));
+        }
+    }
+
+    fn short_deck_str(cards: &str, trips_beat_straights: bool) -> (HandRank, i32) {
+        evaluate_short_deck(CardSet::from(&CardVector::parse(cards)), &short_deck_order(trips_beat_straights))
+    }
+
+    #[test]
+    fn test_short_deck_ace_wraps() {
+        assert_eq!(short_deck_str("Ac 6d 7h 8s 9c", false).0, HandRank::Straight);
+        assert_eq!(short_deck_str("Ac 6c 7c 8c 9c Kd", false).0, HandRank::StraightFlush);
+        assert!(short_deck_str("Ac 6d 7h 8s 9c", false).1 < short_deck_str("6d 7h 8s 9c Tc", false).1);
+        assert!(short_deck_str("Ac 6d 7h 8s 9c", false).1 > short_deck_str("Ac Ad Kh Qs 9c", false).1);
+        assert_eq!(hand_rank(evaluate_str("Ac 6d 7h 8s 9c")), HandRank::HighCard);
+    }
+
+    #[test]
+    fn test_short_deck_order() {
+        let (rank, flush) = short_deck_str("Ac Jc 9c 7c 6c Kd", false);
+        assert_eq!(rank, HandRank::Flush);
+        assert!(flush > short_deck_str("Ac Ad Ah Kc Kd Ks 7c", false).1);
+        assert!(flush < short_deck_str("Ac Ad Ah As Kd", false).1);
+
+        let straight = short_deck_str("Tc 9d 8h 7s 6c", false).1;
+        let trips = short_deck_str("Ac Ad Ah 7s 6c", false).1;
+        assert!(straight > trips);
+
+        let straight = short_deck_str("Tc 9d 8h 7s 6c", true).1;
+        let trips = short_deck_str("Ac Ad Ah 7s 6c", true).1;
+        assert!(trips > straight);
+    }
+}
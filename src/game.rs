@@ -0,0 +1,270 @@
+use strum::IntoEnumIterator;
+
+use super::card::{Suit, Rank, Card, CardSet};
+use super::deck::{make_deck_without, make_short_deck};
+use super::poker_hand::{PokerHand, HandRank};
+use super::low_hand::LowHand;
+use super::wild::WildCards;
+use super::fast_eval;
+
+type ParseError = &'static str;
+
+/// Community card games supported by the equity calculators.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, EnumIter, Display)]
+pub enum Game {
+    #[strum(to_string = "holdem")]
+    HoldEm,
+    #[strum(to_string = "omaha")]
+    Omaha,
+    #[strum(to_string = "omaha8")]
+    OmahaHiLo,
+    /// Six plus hold'em, where straights beat three of a kind
+    #[strum(to_string = "shortdeck")]
+    ShortDeck,
+    /// Six plus hold'em, where three of a kind beats straights
+    #[strum(to_string = "shortdeck-trips")]
+    ShortDeckTrips,
+}
+
+impl Game {
+    /// Smallest and largest number of hole cards dealt to each player.
+    pub fn pocket_sizes(&self) -> (usize, usize) {
+        match self {
+            Game::HoldEm | Game::ShortDeck | Game::ShortDeckTrips => (2, 2),
+            Game::Omaha | Game::OmahaHiLo => (4, 6)
+        }
+    }
+
+    /// Split pot games award half the pot to the best eight-or-better low.
+    pub fn is_hi_lo(&self) -> bool {
+        *self == Game::OmahaHiLo
+    }
+
+    pub fn is_short_deck(&self) -> bool {
+        *self == Game::ShortDeck || *self == Game::ShortDeckTrips
+    }
+
+    /// Short deck games deal only natural sixes through aces.
+    pub fn is_valid_card(&self, card: &Card) -> bool {
+        !self.is_short_deck() || card.rank >= Rank::Six
+    }
+
+    pub fn is_valid_pocket(&self, pocket: &[Card]) -> bool {
+        let (min, max) = self.pocket_sizes();
+        pocket.len() >= min && pocket.len() <= max && pocket.iter().all(|card| self.is_valid_card(card))
+    }
+
+    /// The deck this game is dealt from, less the `dead` cards.
+    pub fn deck_without(&self, dead: &CardSet) -> Vec<Card> {
+        if self.is_short_deck() {
+            let mut deck = make_short_deck();
+            deck.retain(|card| !dead.contains(card));
+            return deck;
+        }
+        return make_deck_without(dead);
+    }
+
+    /// Scores the high hand. Short deck games ignore `wild`.
+    pub fn score(&self, pocket: &[Card], board: &[&Card], board_set: CardSet, wild: &WildCards) -> (HandRank, i32) {
+        match self {
+            Game::HoldEm => score_hold_em(pocket, board, board_set, wild),
+            Game::Omaha | Game::OmahaHiLo => score_omaha(pocket, board, wild),
+            Game::ShortDeck => score_short_deck(pocket, board_set, false),
+            Game::ShortDeckTrips => score_short_deck(pocket, board_set, true)
+        }
+    }
+
+    /// Scores the qualifying low half of a hi/lo hand, using the encoding of
+    /// `LowHand::score` (lower is better), or `None` without a qualifying low.
+    pub fn score_low(&self, pocket: &[Card], board: &[&Card]) -> Option<i32> {
+        match self {
+            Game::OmahaHiLo => {
+                let hole = pocket.iter().collect::<Vec<_>>();
+                LowHand::omaha_ace_to_five(&hole, board, Rank::Eight).map(|hand| hand.score)
+            },
+            _ => None
+        }
+    }
+}
+
+impl std::str::FromStr for Game {
+    type Err = ParseError;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let lower_case = str.to_ascii_lowercase();
+        for game in Self::iter() {
+            if lower_case == game.to_string() {
+                return Ok(game);
+            }
+        }
+        return Err("Invalid Game");
+    }
+}
+
+fn score_hold_em(pocket: &[Card], board: &[&Card], board_set: CardSet, wild: &WildCards) -> (HandRank, i32) {
+    let cards = board_set | CardSet::from(pocket);
+    let wild_cards = wild.for_hand(pocket, board);
+    if cards.len() == pocket.len() + board.len() && wild_cards.is_disjoint(&cards) && fast_eval::can_evaluate(&cards) {
+        let score = fast_eval::evaluate(cards);
+        return (fast_eval::hand_rank(score), score);
+    }
+
+    let mut current = board.to_vec();
+    current.extend(pocket);
+    let hand = PokerHand::build_with(&current, &|card: &Card| wild_cards.contains(card));
+    return (hand.rank, hand.score);
+}
+
+fn score_short_deck(pocket: &[Card], board_set: CardSet, trips_beat_straights: bool) -> (HandRank, i32) {
+    let cards = board_set | CardSet::from(pocket);
+    return fast_eval::evaluate_short_deck(cards, &fast_eval::short_deck_order(trips_beat_straights));
+}
+
+fn score_omaha(pocket: &[Card], board: &[&Card], wild: &WildCards) -> (HandRank, i32) {
+    let cards = pocket.iter().chain(board.iter().cloned()).collect::<CardSet>();
+    let wild_cards = wild.for_hand(pocket, board);
+    let joker = Card::new(Rank::Joker, Suit::Joker);
+    if cards.len() != pocket.len() + board.len() || !wild_cards.is_disjoint(&cards) || cards.contains(&joker) {
+        let hand = PokerHand::build_omaha_with(&pocket.iter().collect::<Vec<_>>(), board, &|card: &Card| wild_cards.contains(card));
+        return (hand.rank, hand.score);
+    }
+
+    let score = score_omaha_natural(pocket, board);
+    return (fast_eval::hand_rank(score), score);
+}
+
+/// Best `fast_eval` score from two of `pocket` and three of `board`, for
+/// distinct natural cards.
+fn score_omaha_natural(pocket: &[Card], board: &[&Card]) -> i32 {
+    let mut best = None;
+    for i in 0..pocket.len() {
+        for j in i + 1..pocket.len() {
+            let mut hole = CardSet::new();
+            hole.insert(&pocket[i]);
+            hole.insert(&pocket[j]);
+
+            for a in 0..board.len() {
+                for b in a + 1..board.len() {
+                    for c in b + 1..board.len() {
+                        let mut hand = hole;
+                        hand.insert(board[a]);
+                        hand.insert(board[b]);
+                        hand.insert(board[c]);
+
+                        let score = fast_eval::evaluate(hand);
+                        if best.is_none_or(|max| score > max) {
+                            best = Some(score);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    return best.expect("Omaha needs at least two hole cards and three board cards");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+
+    fn score(game: Game, pocket: &str, board: &str) -> (HandRank, i32) {
+        let pocket = CardVector::parse(pocket);
+        let board = CardVector::parse(board);
+        let board_refs = board.iter().collect::<Vec<_>>();
+        game.score(&pocket, &board_refs, CardSet::from(&board), &WildCards::new())
+    }
+
+    #[test]
+    fn test_game_parsing() {
+        assert_eq!("holdem".parse::<Game>(), Ok(Game::HoldEm));
+        assert_eq!("Omaha".parse::<Game>(), Ok(Game::Omaha));
+        assert_eq!("shortdeck-trips".parse::<Game>(), Ok(Game::ShortDeckTrips));
+        assert!("razz".parse::<Game>().is_err());
+    }
+
+    #[test]
+    fn test_omaha_score_matches_poker_hand() {
+        for (pocket, board) in [("Ts 9c 8c 7c", "As Ks Qs Js 2d"),
+                                ("2c 3c 4d 5d", "Ac Ad Ah As Kd"),
+                                ("Ac Kc Qc Jc Tc 9c", "2c 3c 4d 5h 8s"),
+                                ("Ah Ad Kh Kd 5c", "Ac Kc 8h 4h 3d")].iter() {
+            let hole = CardVector::parse(pocket);
+            let common = CardVector::parse(board);
+            let expected = PokerHand::build_omaha(&hole.iter().collect::<Vec<_>>(), &common.iter().collect::<Vec<_>>(), &None);
+            assert_eq!(score(Game::Omaha, pocket, board), (expected.rank, expected.score));
+        }
+    }
+
+    #[test]
+    fn test_omaha_fast_path() {
+        for (pocket, board) in [("Ts 9c 8c 7c", "As Ks Qs Js 2d"),
+                                ("Ac Kc Qc Jc Tc 9c", "2c 3c 4d 5h 8s"),
+                                ("Ah Ad Kh Kd 5c", "Ac Kc 8h 4h 3d")].iter() {
+            let hole = CardVector::parse(pocket);
+            let common = CardVector::parse(board);
+            let common_refs = common.iter().collect::<Vec<_>>();
+            let expected = PokerHand::build_omaha(&hole.iter().collect::<Vec<_>>(), &common_refs, &None);
+            assert_eq!(score_omaha_natural(&hole, &common_refs), expected.score);
+        }
+
+        // Wild jokers still take the slow path
+        let hole = CardVector::parse("?? Ts 8c 7c");
+        let common = CardVector::parse("As Ks Qs Js 2d");
+        let common_refs = common.iter().collect::<Vec<_>>();
+        let jokers = WildCards::new().with_jokers(1);
+        assert_eq!(Game::Omaha.score(&hole, &common_refs, CardSet::from(&common), &jokers).0, HandRank::StraightFlush);
+    }
+
+    #[test]
+    fn test_omaha_low_matches_low_hand() {
+        use itertools::Itertools;
+
+        for (pocket, board) in [("Ac 2d Kh Ks", "3c 4d 8h Qs Jc"),
+                                ("Ac Kd Kh Ks", "2c 3d 4h 5s 6c"),
+                                ("Ac 2d 3h 4s", "5c 6d 7h 8s 9c"),
+                                ("Ac 2d 3h 4s 5s 6s", "2c 3d 7h 8s 9c"),
+                                ("Ac 2d Kh Ks", "2c 3d 9h Qs 4c")].iter() {
+            let hole = CardVector::parse(pocket);
+            let common = CardVector::parse(board);
+            // The best qualifying low over every two hole and three board cards
+            let expected = hole.iter()
+                .combinations(2)
+                .cartesian_product(common.iter().combinations(3).collect::<Vec<_>>())
+                .filter_map(|(hole_cards, board_cards)| {
+                    let cards = hole_cards.into_iter().chain(board_cards).collect::<Vec<_>>();
+                    LowHand::ace_to_five_qualified(&cards, Rank::Eight)
+                })
+                .max();
+            assert_eq!(Game::OmahaHiLo.score_low(&hole, &common.iter().collect::<Vec<_>>()), expected.map(|hand| hand.score));
+        }
+    }
+
+    #[test]
+    fn test_hold_em_score() {
+        assert_eq!(score(Game::HoldEm, "Ts 9c", "As Ks Qs Js 2d").0, HandRank::StraightFlush);
+        assert_eq!(score(Game::Omaha, "Ts 9c 8c 7c", "As Ks Qs Js 2d").0, HandRank::Straight);
+    }
+
+    #[test]
+    fn test_pocket_sizes() {
+        assert!(Game::HoldEm.is_valid_pocket(&CardVector::parse("AcKd")));
+        assert!(!Game::HoldEm.is_valid_pocket(&CardVector::parse("AcKdQh")));
+        assert!(Game::Omaha.is_valid_pocket(&CardVector::parse("AcKdQhJs")));
+        assert!(Game::Omaha.is_valid_pocket(&CardVector::parse("AcKdQhJsTs9s")));
+        assert!(!Game::Omaha.is_valid_pocket(&CardVector::parse("AcKd")));
+        assert!(Game::ShortDeck.is_valid_pocket(&CardVector::parse("Ac6d")));
+        assert!(!Game::ShortDeck.is_valid_pocket(&CardVector::parse("Ac5d")));
+    }
+
+    #[test]
+    fn test_short_deck() {
+        assert_eq!(Game::ShortDeck.deck_without(&CardSet::new()).len(), 36);
+        assert_eq!(Game::ShortDeck.deck_without(&CardSet::from(&CardVector::parse("Ac 2c"))).len(), 35);
+
+        assert_eq!(score(Game::ShortDeck, "Ac 6d", "7h 8s 9c Kd Kh").0, HandRank::Straight);
+        assert_eq!(score(Game::HoldEm, "Ac 6d", "7h 8s 9c Kd Kh").0, HandRank::OnePair);
+        assert!(score(Game::ShortDeck, "Ac Kc", "Qc 7c 6c Kd Kh").1 > score(Game::ShortDeck, "Qs Qd", "Qc 7c 6c Kd Kh").1);
+        assert!(score(Game::ShortDeckTrips, "Ac Ad", "As 7c 6c Jd Kh").1 > score(Game::ShortDeckTrips, "Tc 9d", "8s 7c 6c Jd Kh").1);
+    }
+}
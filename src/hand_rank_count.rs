@@ -0,0 +1,77 @@
+use strum::IntoEnumIterator;
+
+use super::poker_hand::HandRank;
+
+pub struct HandRankCount(Vec<usize>);
+
+impl HandRankCount {
+    pub fn new() -> HandRankCount {
+        HandRankCount(vec![0; 1 + HandRank::FiveOfAKind as usize])
+    }
+
+    pub fn for_players(n: usize) -> Vec<HandRankCount> {
+        (0..n).map(|_| HandRankCount::new()).collect()
+    }
+
+    pub fn inc(&mut self, rank: HandRank) {
+        self.0[rank as usize] += 1;
+    }
+
+    pub fn add(&mut self, rank: HandRank, count: usize) {
+        self.0[rank as usize] += count;
+    }
+}
+
+impl Default for HandRankCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::ops::AddAssign<&HandRankCount> for HandRankCount {
+    fn add_assign(&mut self, other: &HandRankCount) {
+        for (count, other_count) in self.0.iter_mut().zip(other.0.iter()) {
+            *count += other_count;
+        }
+    }
+}
+
+impl std::ops::Deref for HandRankCount {
+    type Target = Vec<usize>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for HandRankCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let total = self.0.iter().sum::<usize>() as f32;
+        
+        for rank in HandRank::iter() {
+            // Only wild cards make five of a kind, so leave it out of games
+            // without them rather than show it at zero.
+            if rank == HandRank::FiveOfAKind && self.0[rank as usize] == 0 {
+                continue;
+            }
+            let count = self.0[rank as usize] as f32;
+            let p = 100.0 * count / total;
+            writeln!(f, "{:14} - {:5.2} %%", rank.to_string(), p)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_five_of_a_kind_only_shown_when_made() {
+        let mut count = HandRankCount::new();
+        count.inc(HandRank::OnePair);
+        assert!(!count.to_string().contains("Five of a Kind"));
+
+        count.inc(HandRank::FiveOfAKind);
+        assert!(count.to_string().contains("Five of a Kind"));
+    }
+}
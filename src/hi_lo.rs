@@ -0,0 +1,199 @@
+use super::card::{Card, CardSet};
+use super::game::Game;
+use super::hand_rank_count::HandRankCount;
+use super::hold_em::{find_weighted_winners, for_each_distinct_board, deal_symmetries, game_deck, has_random_pockets, random_pocket_deal, fill_random_pockets};
+use super::deal::{for_each_distinct_deal, for_each_random_deal};
+use super::monte_carlo::{for_each_sample, MonteCarloOptions};
+use super::pot_equity::{PotEquity, award_weighted_pot};
+use super::wild::WildCards;
+
+/// Players holding the best qualifying low, or none if nobody qualifies.
+pub fn find_low_winners(game: Game, pockets: &[Vec<Card>], board: &[&Card]) -> Vec<usize> {
+    let mut vec = Vec::new();
+
+    let mut best_score = None;
+    for (index, pocket) in pockets.iter().enumerate() {
+        if let Some(score) = game.score_low(pocket, board) {
+            if best_score.is_none_or(|min| score < min) {
+                vec.clear();
+                best_score = Some(score);
+            }
+            if best_score == Some(score) {
+                vec.push(index);
+            }
+        }
+    }
+
+    return vec;
+}
+
+/// Awards the pot on `board` `weight` times.
+fn award_board(game: Game, pockets: &[Vec<Card>], board: &[&Card], results: &mut [PotEquity], hand_rank_counts: &mut [HandRankCount], weight: usize) {
+    let high_winners = find_weighted_winners(game, pockets, board, &WildCards::new(), hand_rank_counts, weight);
+    let low_winners = find_low_winners(game, pockets, board);
+    award_weighted_pot(results, &high_winners, &low_winners, weight as i32);
+}
+
+fn merge(stripes: Vec<(Vec<PotEquity>, Vec<HandRankCount>)>, pockets: &[Vec<Card>], hand_rank_counts: &mut [HandRankCount]) -> Vec<PotEquity> {
+    let mut results = vec![PotEquity::new(); pockets.len()];
+    for (stripe_results, stripe_counts) in stripes {
+        for (result, stripe_result) in results.iter_mut().zip(stripe_results) {
+            *result += stripe_result;
+        }
+        for (count, stripe_count) in hand_rank_counts.iter_mut().zip(stripe_counts.iter()) {
+            *count += stripe_count;
+        }
+    }
+    return results;
+}
+
+fn award_deal(game: Game, pockets: &[Vec<Card>], dealt: &[CardSet], results: &mut [PotEquity], hand_rank_counts: &mut [HandRankCount], weight: usize) {
+    let (complete_pockets, complete_board) = fill_random_pockets(pockets, dealt);
+    let board_refs = complete_board.iter().collect::<Vec<_>>();
+    award_board(game, &complete_pockets, &board_refs, results, hand_rank_counts, weight);
+}
+
+/// Exhaustive split pot equity for a hi/lo community card game, with the
+/// `dead` cards never dealt. Empty pockets are dealt every possible random
+/// hand. As in `game_odds`, deals that differ only by swapping suits are
+/// scored once, so `equity` can differ from adding up every pot by rounding.
+pub fn hi_lo_odds(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], hand_rank_counts: &mut [HandRankCount], threads: usize) -> Vec<PotEquity> {
+    let deck = game_deck(game, pockets, board, dead, &WildCards::new());
+    let symmetries = deal_symmetries(pockets, board, &deck, &WildCards::new());
+    let init = || (vec![PotEquity::new(); pockets.len()], HandRankCount::for_players(pockets.len()));
+
+    let stripes = if has_random_pockets(pockets) {
+        let (known, needed) = random_pocket_deal(game, pockets, board);
+        for_each_distinct_deal(&deck, &known, &needed, &symmetries, threads, init,
+            |(results, counts), dealt, weight| award_deal(game, pockets, dealt, results, counts, weight))
+    } else {
+        for_each_distinct_board(&deck, board, &symmetries, threads, init,
+            |(results, counts), complete_board, weight| award_board(game, pockets, complete_board, results, counts, weight))
+    };
+
+    return merge(stripes, pockets, hand_rank_counts);
+}
+
+/// Sampled split pot equity for a hi/lo community card game, with the
+/// `dead` cards never dealt. Empty pockets are dealt a random hand each trial.
+pub fn hi_lo_monte_carlo(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], hand_rank_counts: &mut [HandRankCount], options: &MonteCarloOptions) -> Vec<PotEquity> {
+    let mut deck = game_deck(game, pockets, board, dead, &WildCards::new());
+    let mut results = vec![PotEquity::new(); pockets.len()];
+
+    if has_random_pockets(pockets) {
+        let (known, needed) = random_pocket_deal(game, pockets, board);
+        for_each_random_deal(&mut deck, &known, &needed, options, |dealt| {
+            award_deal(game, pockets, dealt, &mut results, hand_rank_counts, 1);
+        });
+        return results;
+    }
+
+    for_each_sample(&mut deck, board, options, |complete_board| {
+        award_board(game, pockets, complete_board, &mut results, hand_rank_counts, 1);
+    });
+
+    return results;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+
+    fn odds(pockets: &[&str], board: &str) -> Vec<PotEquity> {
+        let pockets = pockets.iter()
+            .map(|pocket| CardVector::parse(pocket).to_vec())
+            .collect::<Vec<_>>();
+        let board = CardVector::parse(board).to_vec();
+        let mut hand_rank_counts = HandRankCount::for_players(pockets.len());
+        hi_lo_odds(Game::OmahaHiLo, &pockets, &board, &[], &mut hand_rank_counts, 1)
+    }
+
+    #[test]
+    fn test_no_low_board() {
+        let results = odds(&["Ac Kd 2h 3h", "Ad Kh 4s 5s"], "Qs Jc Tc 9h 9d");
+        assert_eq!(results[0].equity, 0.5);
+        assert_eq!(results[1].equity, 0.5);
+        assert_eq!(results[0].high.splits, 1);
+        assert_eq!(results[0].low.total(), 0);
+    }
+
+    #[test]
+    fn test_split_high_and_low() {
+        let results = odds(&["Ac Ad Kc Kd", "2h 3h Ts Js"], "As 4c 7d 8h Qh");
+        assert_eq!(results[0].equity, 0.5);
+        assert_eq!(results[1].equity, 0.5);
+        assert_eq!(results[0].high.wins, 1);
+        assert_eq!(results[1].low.wins, 1);
+    }
+
+    #[test]
+    fn test_scoop() {
+        let results = odds(&["Ac 2d 3c Kd", "Qh Qs Ts Js"], "As 4c 5d 8h Kh");
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(results[0].scoops, 1);
+        assert_eq!(results[1].equity, 0.0);
+    }
+
+    #[test]
+    fn test_quartered() {
+        let results = odds(&["Ac 2d Kc Kd", "Ah 2h Ts Js", "Qh Qs 9s 9d"], "Ks 4c 5d 8h Kh");
+        assert_eq!(results[0].equity, 0.75);
+        assert_eq!(results[1].equity, 0.25);
+        assert_eq!(results[2].equity, 0.0);
+    }
+
+    #[test]
+    fn test_equity_sums_to_one_per_pot() {
+        let results = odds(&["Ac 2d 3c Kd", "Qh Qs Ts Js", "5h 6h 7s 8s"], "As 4c 9d");
+        let pots = results[0].pots as f64;
+        let total = results.iter().map(|result| result.equity).sum::<f64>();
+        assert!((total - pots).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_random_opponent() {
+        let results = odds(&["Ac 2d 3c Kd", ""], "As 4c 5d 8h Kh");
+        assert_eq!(results[0].pots, 123410);
+        assert!((results[0].equity + results[1].equity - 123410.0).abs() < 1e-6);
+        assert!(results[0].equity_pct() > 75.0);
+    }
+
+    #[test]
+    fn test_suit_symmetry_matches_full_enumeration() {
+        use crate::hold_em::{find_game_winners, for_each_board};
+        use crate::pot_equity::award_pot;
+
+        let counts = |result: &PotEquity| ((result.high.wins, result.high.losses, result.high.splits),
+                                           (result.low.wins, result.low.losses, result.low.splits),
+                                           result.scoops, result.pots);
+
+        for (pockets, board) in [(vec!["Ac 2c Kc Qc", "3h 4h 5h 6h"], "7s 7d"),
+                                 (vec!["Ac Ad 2c 2d", ""], "3h 4s 5h Kh Ks")].iter() {
+            let pockets = pockets.iter().map(|pocket| CardVector::parse(pocket).to_vec()).collect::<Vec<_>>();
+            let board = CardVector::parse(board).to_vec();
+            let deck = game_deck(Game::OmahaHiLo, &pockets, &board, &[], &WildCards::new());
+            assert!(deal_symmetries(&pockets, &board, &deck, &WildCards::new()).len() > 1);
+
+            let init = || (vec![PotEquity::new(); pockets.len()], HandRankCount::for_players(pockets.len()));
+            let (full, full_counts) = if has_random_pockets(&pockets) {
+                let (known, needed) = random_pocket_deal(Game::OmahaHiLo, &pockets, &board);
+                crate::deal::for_each_deal(&deck, &known, &needed, 1, init,
+                    |(results, counts), dealt| award_deal(Game::OmahaHiLo, &pockets, dealt, results, counts, 1))
+            } else {
+                for_each_board(&deck, &board, 1, init, |(results, counts), complete_board| {
+                    let high_winners = find_game_winners(Game::OmahaHiLo, &pockets, complete_board, &WildCards::new(), counts);
+                    award_pot(results, &high_winners, &find_low_winners(Game::OmahaHiLo, &pockets, complete_board));
+                })
+            }.pop().unwrap();
+
+            let mut hand_rank_counts = HandRankCount::for_players(pockets.len());
+            let results = hi_lo_odds(Game::OmahaHiLo, &pockets, &board, &[], &mut hand_rank_counts, 2);
+            for i in 0..pockets.len() {
+                assert_eq!(counts(&results[i]), counts(&full[i]));
+                assert!((results[i].equity - full[i].equity).abs() < 1e-6);
+                assert_eq!(*hand_rank_counts[i], *full_counts[i]);
+            }
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use std::cmp::Ordering;
+use itertools::Itertools;
+
+use super::card::{Card, CardSet};
+use super::game::Game;
+use super::win_lose_split::WinLoseSplit;
+use super::hand_rank_count::HandRankCount;
+use super::wild::WildCards;
+use super::deal::{deal_count, for_each_distinct_deal};
+use super::suit_symmetry::{SuitPermutation, symmetries};
+
+pub fn find_winners(pockets: &[Vec<Card>], board: &[&Card], hand_rank_counts: &mut [HandRankCount]) -> Vec<usize> {
+    find_game_winners(Game::HoldEm, pockets, board, &WildCards::new(), hand_rank_counts)
+}
+
+pub fn find_game_winners(game: Game, pockets: &[Vec<Card>], board: &[&Card], wild: &WildCards, hand_rank_counts: &mut [HandRankCount]) -> Vec<usize> {
+    find_weighted_winners(game, pockets, board, wild, hand_rank_counts, 1)
+}
+
+/// Like `find_game_winners`, counting each hand rank `weight` times.
+pub fn find_weighted_winners(game: Game, pockets: &[Vec<Card>], board: &[&Card], wild: &WildCards, hand_rank_counts: &mut [HandRankCount], weight: usize) -> Vec<usize> {
+    let mut vec = Vec::new();
+    let board_set = board.iter().cloned().collect::<CardSet>();
+    
+    let mut best_score = None;
+    for (index, pocket) in pockets.iter().enumerate() {
+        let (rank, score) = game.score(pocket, board, board_set, wild);
+
+        hand_rank_counts[index].add(rank, weight);
+        
+        if let Some(max) = best_score {
+            match score.cmp(&max) {
+                Ordering::Equal => vec.push(index),
+                Ordering::Greater => {
+                    vec.clear();
+                    vec.push(index);
+                    best_score = Some(score)
+                },
+                Ordering::Less => {}
+            }
+        } else {
+            vec.push(index);
+            best_score = Some(score);
+        }
+    }
+    
+    return vec;
+}
+
+pub fn record_winners(results: &mut [WinLoseSplit], winners: &[usize]) {
+    record_weighted_winners(results, winners, 1)
+}
+
+fn record_weighted_winners(results: &mut [WinLoseSplit], winners: &[usize], weight: i32) {
+    for (index, result) in results.iter_mut().enumerate() {
+        if winners.contains(&index) {
+            if winners.len() == 1 {
+                result.wins += weight;
+            } else {
+                result.splits += weight;
+            }
+        } else {
+            result.losses += weight;
+        }
+    }
+}
+
+pub fn dead_cards(pockets: &[Vec<Card>], board: &[Card]) -> CardSet {
+    pockets.iter()
+        .flatten()
+        .chain(board.iter())
+        .collect()
+}
+
+/// The cards left to deal: the game's deck less the pockets, board and
+/// `dead` cards, plus any jokers in play that are not already dealt.
+pub fn game_deck(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], wild: &WildCards) -> Vec<Card> {
+    let known = pockets.iter().flatten().chain(board.iter()).chain(dead.iter()).cloned().collect::<Vec<_>>();
+    let mut deck = game.deck_without(&CardSet::from(known.as_slice()));
+    wild.add_jokers(&mut deck, &known);
+    return deck;
+}
+
+pub fn hold_em_odds(pockets: &[Vec<Card>], board: &[Card], hand_rank_counts: &mut [HandRankCount]) -> Vec<WinLoseSplit> {
+    hold_em_odds_threaded(pockets, board, hand_rank_counts, 1)
+}
+
+pub fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Visits every completion of `board` drawn from `deck`, striping the first
+/// card drawn across `threads` so each thread only generates its own
+/// boards. Each thread accumulates into its own value from `init`; the
+/// accumulators are returned in stripe order so merging is deterministic.
+pub fn for_each_board<T, I, V>(deck: &[Card], board: &[Card], threads: usize, init: I, visit: V) -> Vec<T>
+where T: Send, I: Fn() -> T + Sync, V: Fn(&mut T, &[&Card]) + Sync {
+    let n = 5 - board.len();
+    let threads = if n == 0 { 1 } else { threads.max(1) };
+
+    std::thread::scope(|scope| {
+        let handles = (0..threads)
+            .map(|stripe| {
+                let init = &init;
+                let visit = &visit;
+                scope.spawn(move || {
+                    let mut acc = init();
+                    if n == 0 {
+                        visit(&mut acc, &board.iter().collect::<Vec<_>>());
+                    } else {
+                        for (index, card) in deck.iter().enumerate().skip(stripe).step_by(threads) {
+                            for rest in deck[index + 1..].iter().combinations(n - 1) {
+                                let complete_board = board.iter().chain(std::iter::once(card)).chain(rest).collect::<Vec<_>>();
+                                visit(&mut acc, &complete_board);
+                            }
+                        }
+                    }
+                    acc
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Like `for_each_board`, but visits only the lowest of each class of
+/// run-outs related by one of the suit `symmetries`, along with the number
+/// of run-outs in its class. Only the canonical run-outs are generated, so
+/// without symmetries this is `for_each_board`.
+pub fn for_each_distinct_board<T, I, V>(deck: &[Card], board: &[Card], symmetries: &[SuitPermutation], threads: usize, init: I, visit: V) -> Vec<T>
+where T: Send, I: Fn() -> T + Sync, V: Fn(&mut T, &[&Card], usize) + Sync {
+    if symmetries.len() <= 1 {
+        return for_each_board(deck, board, threads, init, |acc, complete_board| visit(acc, complete_board, 1));
+    }
+
+    let board_set = CardSet::from(board);
+    for_each_distinct_deal(deck, &[board_set], &[5 - board.len()], symmetries, threads, init,
+        |acc, dealt, weight| {
+            let run_out = (dealt[0] - board_set).to_vec();
+            let complete_board = board.iter().chain(run_out.iter()).collect::<Vec<_>>();
+            visit(acc, &complete_board, weight);
+        })
+}
+
+/// The suit permutations under which a deal with these pockets, board, deck
+/// and wild cards is unchanged. Empty pockets, dealt at random, do not tell
+/// suits apart. Hand dependent wild rules might, so they leave only the
+/// identity.
+pub fn deal_symmetries(pockets: &[Vec<Card>], board: &[Card], deck: &[Card], wild: &WildCards) -> Vec<SuitPermutation> {
+    if wild.has_hand_rules() {
+        return vec![SuitPermutation::identity()];
+    }
+
+    let mut fixed = pockets.iter()
+        .map(|pocket| CardSet::from(pocket.as_slice()))
+        .collect::<Vec<_>>();
+    fixed.push(CardSet::from(board));
+    fixed.push(deck.iter().collect());
+    fixed.push(wild.cards);
+    return symmetries(&fixed);
+}
+
+/// Whether any pocket is empty, to be dealt at random.
+pub fn has_random_pockets(pockets: &[Vec<Card>]) -> bool {
+    pockets.iter().any(|pocket| pocket.is_empty())
+}
+
+/// What is left to deal when the empty pockets are dealt at random: the
+/// rest of the board first, then each random pocket with the game's
+/// smallest pocket size. Returns the known cards and the number still
+/// needed for each, in the form taken by `deal::for_each_deal`.
+pub fn random_pocket_deal(game: Game, pockets: &[Vec<Card>], board: &[Card]) -> (Vec<CardSet>, Vec<usize>) {
+    let (pocket_size, _max) = game.pocket_sizes();
+    let mut known = vec![CardSet::from(board)];
+    let mut needed = vec![5 - board.len()];
+    for _pocket in pockets.iter().filter(|pocket| pocket.is_empty()) {
+        known.push(CardSet::new());
+        needed.push(pocket_size);
+    }
+    return (known, needed);
+}
+
+/// The pockets and board of one random deal from `random_pocket_deal`.
+pub fn fill_random_pockets(pockets: &[Vec<Card>], dealt: &[CardSet]) -> (Vec<Vec<Card>>, Vec<Card>) {
+    let mut random = dealt[1..].iter();
+    let complete_pockets = pockets.iter()
+        .map(|pocket| if pocket.is_empty() { random.next().unwrap().to_vec() } else { pocket.clone() })
+        .collect();
+    return (complete_pockets, dealt[0].to_vec());
+}
+
+/// Number of deals `game_odds` enumerates, or `None` when it does not fit
+/// in a `u64`.
+pub fn game_deal_count(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], wild: &WildCards) -> Option<u64> {
+    let (_known, needed) = random_pocket_deal(game, pockets, board);
+    deal_count(game_deck(game, pockets, board, dead, wild).len(), &needed)
+}
+
+fn random_pocket_odds(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], wild: &WildCards, threads: usize) -> Vec<(Vec<WinLoseSplit>, Vec<HandRankCount>)> {
+    let deck = game_deck(game, pockets, board, dead, wild);
+    let (known, needed) = random_pocket_deal(game, pockets, board);
+
+    let symmetries = deal_symmetries(pockets, board, &deck, wild);
+
+    for_each_distinct_deal(&deck, &known, &needed, &symmetries, threads,
+        || (vec![WinLoseSplit::new(); pockets.len()], HandRankCount::for_players(pockets.len())),
+        |(results, counts), dealt, weight| {
+            let (complete_pockets, complete_board) = fill_random_pockets(pockets, dealt);
+            let board_refs = complete_board.iter().collect::<Vec<_>>();
+            let winners = find_weighted_winners(game, &complete_pockets, &board_refs, wild, counts, weight);
+            record_weighted_winners(results, &winners, weight as i32);
+        })
+}
+
+pub fn hold_em_odds_threaded(pockets: &[Vec<Card>], board: &[Card], hand_rank_counts: &mut [HandRankCount], threads: usize) -> Vec<WinLoseSplit> {
+    game_odds(Game::HoldEm, pockets, board, &[], &WildCards::new(), hand_rank_counts, threads)
+}
+
+/// Exhaustively enumerates every completion of `board` for a community card
+/// game, with the cards in `wild` wild and the `dead` cards never dealt.
+/// Deals that differ only by swapping suits the deal does not tell apart
+/// are scored once and counted for each. Empty pockets are dealt
+/// every possible random hand; a deck with more than one joker is not
+/// supported with random pockets.
+pub fn game_odds(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], wild: &WildCards, hand_rank_counts: &mut [HandRankCount], threads: usize) -> Vec<WinLoseSplit> {
+    let stripes = if has_random_pockets(pockets) {
+        random_pocket_odds(game, pockets, board, dead, wild, threads)
+    } else {
+        let deck = game_deck(game, pockets, board, dead, wild);
+        let symmetries = deal_symmetries(pockets, board, &deck, wild);
+        for_each_distinct_board(&deck, board, &symmetries, threads,
+            || (vec![WinLoseSplit::new(); pockets.len()], HandRankCount::for_players(pockets.len())),
+            |(results, counts), complete_board, weight| {
+                let winners = find_weighted_winners(game, pockets, complete_board, wild, counts, weight);
+                record_weighted_winners(results, &winners, weight as i32);
+            })
+    };
+
+    let mut results = vec![WinLoseSplit::new(); pockets.len()];
+    for (stripe_results, stripe_counts) in stripes {
+        for (result, stripe_result) in results.iter_mut().zip(stripe_results) {
+            *result += stripe_result;
+        }
+        for (count, stripe_count) in hand_rank_counts.iter_mut().zip(stripe_counts.iter()) {
+            *count += stripe_count;
+        }
+    }
+    
+    return results;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use crate::poker_hand::HandRank;
+    use crate::deck::make_deck_without;
+
+    fn odds(pockets: &[&str], board: &str) -> Vec<WinLoseSplit> {
+        let pockets = pockets.iter()
+            .map(|pocket| CardVector::parse(pocket).to_vec())
+            .collect::<Vec<_>>();
+        let board = CardVector::parse(board).to_vec();
+        let mut hand_rank_counts = pockets.iter()
+            .map(|_| HandRankCount::new())
+            .collect::<Vec<_>>();
+        hold_em_odds(&pockets, &board, &mut hand_rank_counts)
+    }
+
+    #[test]
+    fn test_complete_board() {
+        let results = odds(&["AcAd", "KcKd"], "2s 7h 9d Jc 3s");
+        assert_eq!(results[0].wins, 1);
+        assert_eq!(results[1].losses, 1);
+    }
+
+    #[test]
+    fn test_split_board() {
+        let results = odds(&["2c3d", "2d3c"], "As Ks Qd Jh Ts");
+        assert_eq!(results[0].splits, 1);
+        assert_eq!(results[1].splits, 1);
+    }
+
+    #[test]
+    fn test_river_to_come() {
+        let results = odds(&["AcAd", "KcKd"], "2s 7h 9d Jc");
+        assert_eq!(results[0].total(), 44);
+        assert_eq!(results[0].wins, results[1].losses);
+        assert_eq!(results[1].wins, 2);
+    }
+
+    #[test]
+    fn test_threaded_matches_single_thread() {
+        let pockets = vec![CardVector::parse("AcAd").to_vec(),
+                           CardVector::parse("KcKd").to_vec(),
+                           CardVector::parse("7h6h").to_vec()];
+        let board = CardVector::parse("2s 7c 9d").to_vec();
+
+        let mut single_counts = vec![HandRankCount::new(), HandRankCount::new(), HandRankCount::new()];
+        let single = hold_em_odds(&pockets, &board, &mut single_counts);
+
+        let mut threaded_counts = vec![HandRankCount::new(), HandRankCount::new(), HandRankCount::new()];
+        let threaded = hold_em_odds_threaded(&pockets, &board, &mut threaded_counts, 3);
+
+        for i in 0..pockets.len() {
+            assert_eq!(single[i].wins, threaded[i].wins);
+            assert_eq!(single[i].losses, threaded[i].losses);
+            assert_eq!(single[i].splits, threaded[i].splits);
+            assert_eq!(*single_counts[i], *threaded_counts[i]);
+        }
+    }
+
+    #[test]
+    fn test_omaha_odds() {
+        let pockets = vec![CardVector::parse("Ts 9c 8c 7c").to_vec(),
+                           CardVector::parse("Ah Kd 7d 2h").to_vec()];
+        let board = CardVector::parse("As Ks Qs Js").to_vec();
+        let mut hand_rank_counts = vec![HandRankCount::new(), HandRankCount::new()];
+
+        let hold_em = game_odds(Game::HoldEm, &pockets, &board, &[], &WildCards::new(), &mut hand_rank_counts, 1);
+        assert_eq!(hold_em[0].wins, 40);
+
+        // The Ts alone does not make a royal flush in Omaha
+        let mut hand_rank_counts = vec![HandRankCount::new(), HandRankCount::new()];
+        let omaha = game_odds(Game::Omaha, &pockets, &board, &[], &WildCards::new(), &mut hand_rank_counts, 1);
+        assert_eq!(omaha[0].total(), 40);
+        assert!(omaha[0].losses > 0);
+        assert_eq!(hand_rank_counts[0][HandRank::StraightFlush as usize], 0);
+        assert_eq!(hand_rank_counts[0][HandRank::Straight as usize], 40);
+    }
+
+    #[test]
+    fn test_wild_odds() {
+        let pockets = vec![CardVector::parse("AcAd").to_vec(), CardVector::parse("KcKd").to_vec()];
+        let board = CardVector::parse("2s 7h 9d Jc").to_vec();
+
+        // The joker is a 45th river card and fills the aces' trips
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let jokers = game_odds(Game::HoldEm, &pockets, &board, &[], &WildCards::new().with_jokers(1), &mut hand_rank_counts, 2);
+        assert_eq!(jokers[0].total(), 45);
+        assert_eq!(jokers[1].wins, 2);
+
+        // With the 2s wild, a king, queen or ten wins for the kings and an
+        // eight makes the same straight for both
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let deuces = game_odds(Game::HoldEm, &pockets, &board, &[], &WildCards::new().with_rule(Card::is_deuce), &mut hand_rank_counts, 2);
+        assert_eq!(deuces[0].total(), 44);
+        assert_eq!(deuces[1].wins, 10);
+        assert_eq!(deuces[1].splits, 4);
+        assert_eq!(hand_rank_counts[0][HandRank::OnePair as usize], 0);
+    }
+
+    #[test]
+    fn test_low_hole_card_wild_odds() {
+        let pockets = vec![CardVector::parse("KcKd").to_vec(), CardVector::parse("3cAd").to_vec()];
+        let board = CardVector::parse("3h Ks Qd 7c").to_vec();
+        let wild = WildCards::new().with_hand_rule(crate::wild::LowHoleCard);
+
+        // Every king is wild for the first player and every three for the
+        // second, who wins with four aces and a natural king kicker on the
+        // last two aces and shares a royal flush on the Jd or Td
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let results = game_odds(Game::HoldEm, &pockets, &board, &[], &wild, &mut hand_rank_counts, 2);
+        assert_eq!(results[0].total(), 44);
+        assert_eq!(results[1].wins, 2);
+        assert_eq!(results[1].splits, 2);
+        assert_eq!(hand_rank_counts[0][HandRank::FullHouse as usize], 0);
+    }
+
+    #[test]
+    fn test_random_opponent() {
+        let pockets = vec![CardVector::parse("AcAd").to_vec(), Vec::new()];
+        let board = CardVector::parse("2s 7h 9d Jc").to_vec();
+        assert_eq!(game_deal_count(Game::HoldEm, &pockets, &board, &[], &WildCards::new()), Some(46 * 990));
+
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let results = game_odds(Game::HoldEm, &pockets, &board, &[], &WildCards::new(), &mut hand_rank_counts, 3);
+        assert_eq!(results[0].total(), 46 * 990);
+        assert_eq!(results[0].wins, results[1].losses);
+
+        // The same as playing every opponent pocket in turn
+        let mut expected = WinLoseSplit::new();
+        let dead = dead_cards(&pockets, &board);
+        for opponent in make_deck_without(&dead).into_iter().combinations(2) {
+            let pockets = vec![pockets[0].clone(), opponent];
+            expected += hold_em_odds(&pockets, &board, &mut HandRankCount::for_players(2))[0];
+        }
+        assert_eq!(results[0].wins, expected.wins);
+        assert_eq!(results[0].splits, expected.splits);
+        assert_eq!(hand_rank_counts[1].iter().sum::<usize>(), 46 * 990);
+    }
+
+    #[test]
+    fn test_dead_cards() {
+        let pockets = vec![CardVector::parse("AcAd").to_vec(), CardVector::parse("KcKd").to_vec()];
+        let board = CardVector::parse("2s 7h 9d Jc").to_vec();
+        let dead = CardVector::parse("Kh Ks").to_vec();
+        assert_eq!(game_deal_count(Game::HoldEm, &pockets, &board, &dead, &WildCards::new()), Some(42));
+
+        // Both kings are dead, so the kings are drawing dead
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let results = game_odds(Game::HoldEm, &pockets, &board, &dead, &WildCards::new(), &mut hand_rank_counts, 2);
+        assert_eq!(results[0].wins, 42);
+        assert_eq!(results[1].wins, 0);
+        assert_eq!(hand_rank_counts[1][HandRank::Triplets as usize], 0);
+    }
+
+    #[test]
+    fn test_suit_symmetry_matches_full_enumeration() {
+        let cases = [(Game::HoldEm, vec!["As Ks", "Qs Js"], "7s", "", WildCards::new()),
+                     (Game::HoldEm, vec!["Ac Ad", "Kc Kd"], "2s 7h", "Kh Ks", WildCards::new()),
+                     (Game::HoldEm, vec!["Ac Kc", "Qc Jc", "Th Ts"], "2d 5h 5s", "", WildCards::new()),
+                     (Game::HoldEm, vec!["Ac Ad", "Kh Ks"], "9c 9d", "", WildCards::new().with_rule(Card::is_deuce)),
+                     (Game::Omaha, vec!["Ac Ad Kc Kd", "Qh Qs Jh Js"], "2c 2d 2h", "", WildCards::new()),
+                     (Game::ShortDeck, vec!["As Ks", "Qh Qd"], "6c 7c", "", WildCards::new())];
+
+        for (game, pockets, board, dead, wild) in cases.iter() {
+            let pockets = pockets.iter().map(|pocket| CardVector::parse(pocket).to_vec()).collect::<Vec<_>>();
+            let board = CardVector::parse(board).to_vec();
+            let dead = CardVector::parse(dead).to_vec();
+            let deck = game_deck(*game, &pockets, &board, &dead, wild);
+            assert!(deal_symmetries(&pockets, &board, &deck, wild).len() > 1);
+
+            let (full, full_counts) = for_each_board(&deck, &board, 1,
+                || (vec![WinLoseSplit::new(); pockets.len()], HandRankCount::for_players(pockets.len())),
+                |(results, counts), complete_board| {
+                    let winners = find_game_winners(*game, &pockets, complete_board, wild, counts);
+                    record_winners(results, &winners);
+                }).pop().unwrap();
+
+            let mut hand_rank_counts = HandRankCount::for_players(pockets.len());
+            let results = game_odds(*game, &pockets, &board, &dead, wild, &mut hand_rank_counts, 2);
+            for i in 0..pockets.len() {
+                assert_eq!((results[i].wins, results[i].losses, results[i].splits), (full[i].wins, full[i].losses, full[i].splits));
+                assert_eq!(*hand_rank_counts[i], *full_counts[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_suit_symmetry_with_random_pockets() {
+        use crate::deal::for_each_deal;
+
+        let pockets = vec![CardVector::parse("Ac Ad").to_vec(), Vec::new()];
+        let board = CardVector::parse("Kh Ks 7h 7s").to_vec();
+        let deck = game_deck(Game::HoldEm, &pockets, &board, &[], &WildCards::new());
+        assert_eq!(deal_symmetries(&pockets, &board, &deck, &WildCards::new()).len(), 4);
+
+        let (known, needed) = random_pocket_deal(Game::HoldEm, &pockets, &board);
+        let (full, full_counts) = for_each_deal(&deck, &known, &needed, 1,
+            || (vec![WinLoseSplit::new(); pockets.len()], HandRankCount::for_players(pockets.len())),
+            |(results, counts), dealt| {
+                let (complete_pockets, complete_board) = fill_random_pockets(&pockets, dealt);
+                let board_refs = complete_board.iter().collect::<Vec<_>>();
+                let winners = find_game_winners(Game::HoldEm, &complete_pockets, &board_refs, &WildCards::new(), counts);
+                record_winners(results, &winners);
+            }).pop().unwrap();
+
+        let mut hand_rank_counts = HandRankCount::for_players(pockets.len());
+        let results = game_odds(Game::HoldEm, &pockets, &board, &[], &WildCards::new(), &mut hand_rank_counts, 2);
+        for i in 0..pockets.len() {
+            assert_eq!((results[i].wins, results[i].losses, results[i].splits), (full[i].wins, full[i].losses, full[i].splits));
+            assert_eq!(*hand_rank_counts[i], *full_counts[i]);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+extern crate strum;
+#[macro_use] extern crate strum_macros;
+
+pub mod card;
+pub mod deck;
+pub mod poker_hand;
+pub mod fast_eval;
+pub mod game;
+pub mod low_hand;
+pub mod win_lose_split;
+pub mod pot_equity;
+pub mod hand_rank_count;
+pub mod hold_em;
+pub mod monte_carlo;
+pub mod hi_lo;
+pub mod deal;
+pub mod stud;
+pub mod draw;
+pub mod discard;
+pub mod wild;
+pub mod range;
+pub mod range_equity;
+pub mod suit_symmetry;
+pub mod preflop_table;
+
+pub use card::{Suit, Rank, Card, CardVector, CardSet, IsWildCard, fmt_cards};
+pub use deck::{make_deck, make_deck_with_jokers, make_short_deck, make_deck_without, make_shuffled_deck, remove_card, remove_all_cards};
+pub use poker_hand::{PokerHand, HandRank, BadugiHand};
+pub use low_hand::LowHand;
+pub use win_lose_split::WinLoseSplit;
+pub use pot_equity::PotEquity;
+pub use hand_rank_count::HandRankCount;
+pub use game::Game;
+pub use hold_em::{hold_em_odds, hold_em_odds_threaded, game_odds, default_thread_count, dead_cards, game_deck, game_deal_count, has_random_pockets, find_winners, find_game_winners, record_winners};
+pub use monte_carlo::{hold_em_monte_carlo, game_monte_carlo, MonteCarloOptions};
+pub use hi_lo::{hi_lo_odds, hi_lo_monte_carlo};
+pub use stud::{StudHand, stud_odds, stud_monte_carlo, stud_deal_count, validate_stud_hands};
+pub use draw::{DrawGame, DrawHand, draw_odds, validate_draw_hands, draw_monte_carlo, draw_deal_count, badugi_odds, badugi_monte_carlo, badugi_deal_count};
+pub use discard::{HoldResult, evaluate_hold, advise_discard};
+pub use wild::{WildCards, WildRule, LowHoleCard};
+pub use range::{Combo, HandRange};
+pub use range_equity::{RangeResult, RangeEquity, range_odds, range_monte_carlo, range_deal_count};
+pub use preflop_table::{PreflopTable, evaluator_checksum};
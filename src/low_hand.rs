@@ -0,0 +1,372 @@
+use std::cmp::Ordering;
+use itertools::Itertools;
+
+use super::card::{Rank, Card, fmt_cards};
+use super::poker_hand::HandRank;
+use super::fast_eval::{low_bit, score_low_mask};
+
+fn low_rank(card: &Card) -> Rank {
+    if card.rank == Rank::Ace {
+        Rank::LowAce
+    } else {
+        card.rank
+    }
+}
+
+/// Orders five cards the way `PokerHand` does: largest sets first, then by
+/// rank, highest first.
+fn group_cards(cards: &[&Card], rank_of: fn(&Card) -> Rank) -> (HandRank, Vec<Card>) {
+    let groups = cards.iter()
+        .map(|card| card.scored_as(rank_of(card)))
+        .sorted_by(|a, b| b.scoring_rank.cmp(&a.scoring_rank))
+        .group_by(|card| card.scoring_rank)
+        .into_iter()
+        .map(|(_rank, group)| group.collect::<Vec<_>>())
+        .sorted_by(|a, b| b.len().cmp(&a.len()).then(b[0].scoring_rank.cmp(&a[0].scoring_rank)))
+        .collect::<Vec<_>>();
+
+    let sizes = groups.iter().map(|group| group.len()).collect::<Vec<_>>();
+    let hand_rank = match sizes.as_slice() {
+        [5] => HandRank::FiveOfAKind,
+        [4, 1] => HandRank::Quads,
+        [3, 2] => HandRank::FullHouse,
+        [3, 1, 1] => HandRank::Triplets,
+        [2, 2, 1] => HandRank::TwoPair,
+        [2, 1, 1, 1] => HandRank::OnePair,
+        _ => HandRank::HighCard
+    };
+
+    return (hand_rank, groups.into_iter().flatten().collect());
+}
+
+fn high_rank(card: &Card) -> Rank {
+    card.rank
+}
+
+fn is_flush(cards: &[Card]) -> bool {
+    cards.len() == 5 && cards.iter().all(|card| card.suit == cards[0].suit)
+}
+
+/// Aces only play high, so A-5-4-3-2 is not a straight.
+fn is_straight(cards: &[Card]) -> bool {
+    cards.len() == 5
+        && cards.windows(2).all(|pair| pair[0].scoring_rank as usize == pair[1].scoring_rank as usize + 1)
+}
+
+fn score_cards(hand_rank: HandRank, cards: &[Card]) -> i32 {
+    return cards.iter()
+        .fold(hand_rank as i32, |acc, card| acc * 16 + (card.scoring_rank as i32));
+}
+
+/// A lowball hand. `score` uses the same encoding as `PokerHand::score`, but
+/// a lower score is a better low, so the `Ord` implementation is reversed:
+/// the better low hand always compares as greater.
+pub struct LowHand {
+    pub rank: HandRank,
+    pub cards: Vec<Card>,
+    pub score: i32
+}
+
+impl LowHand {
+    /// Panics on fewer than five cards: a shorter hand would otherwise score
+    /// as a better low than any complete one.
+    fn best<F>(all_cards: &[&Card], score_five: F) -> LowHand
+    where F: Fn(&[&Card]) -> LowHand {
+        assert!(all_cards.len() >= 5, "A low hand needs at least five cards");
+        if all_cards.len() == 5 {
+            return score_five(all_cards);
+        }
+
+        return all_cards.iter()
+            .cloned()
+            .combinations(5)
+            .map(|cards| score_five(&cards))
+            .max()
+            .unwrap();
+    }
+
+    fn ace_to_five_five(cards: &[&Card]) -> LowHand {
+        let (rank, cards) = group_cards(cards, low_rank);
+        let score = score_cards(rank, &cards);
+        LowHand { rank: rank, cards: cards, score: score }
+    }
+
+    fn deuce_to_seven_five(cards: &[&Card]) -> LowHand {
+        let (mut rank, cards) = group_cards(cards, high_rank);
+        if rank == HandRank::HighCard {
+            rank = match (is_straight(&cards), is_flush(&cards)) {
+                (true, true) => HandRank::StraightFlush,
+                (false, true) => HandRank::Flush,
+                (true, false) => HandRank::Straight,
+                (false, false) => HandRank::HighCard
+            };
+        }
+
+        let score = score_cards(rank, &cards);
+        LowHand { rank: rank, cards: cards, score: score }
+    }
+
+    /// Best deuce-to-seven low: aces are always high and straights and
+    /// flushes count against the hand, so 7-5-4-3-2 is the nuts.
+    pub fn deuce_to_seven(all_cards: &[&Card]) -> LowHand {
+        LowHand::best(all_cards, LowHand::deuce_to_seven_five)
+    }
+
+    /// Best ace-to-five low: aces are low and straights and flushes are
+    /// ignored, so 5-4-3-2-A is the nuts.
+    pub fn ace_to_five(all_cards: &[&Card]) -> LowHand {
+        LowHand::best(all_cards, LowHand::ace_to_five_five)
+    }
+
+    /// Best ace-to-five low that is no worse than `qualifier` high, e.g.
+    /// `Rank::Eight` for eight-or-better.
+    pub fn ace_to_five_qualified(all_cards: &[&Card], qualifier: Rank) -> Option<LowHand> {
+        let hand = LowHand::ace_to_five(all_cards);
+        if hand.qualifies(qualifier) {
+            Some(hand)
+        } else {
+            None
+        }
+    }
+
+    /// Best qualifying Omaha low: exactly two cards from `hole` and three
+    /// from `board`. Only five distinct ranks no higher than `qualifier`
+    /// qualify, so the search runs over rank masks and builds the hand once.
+    pub fn omaha_ace_to_five(hole: &[&Card], board: &[&Card], qualifier: Rank) -> Option<LowHand> {
+        let mut best: Option<(i32, [&Card; 5])> = None;
+        for i in 0..hole.len() {
+            for j in i + 1..hole.len() {
+                let hole_mask = low_bit(hole[i], qualifier) | low_bit(hole[j], qualifier);
+                if hole_mask.count_ones() != 2 {
+                    continue;
+                }
+
+                for a in 0..board.len() {
+                    for b in a + 1..board.len() {
+                        for c in b + 1..board.len() {
+                            let mask = hole_mask
+                                | low_bit(board[a], qualifier)
+                                | low_bit(board[b], qualifier)
+                                | low_bit(board[c], qualifier);
+                            if mask.count_ones() != 5 {
+                                continue;
+                            }
+
+                            let score = score_low_mask(mask);
+                            if best.is_none_or(|(min, _cards)| score < min) {
+                                best = Some((score, [hole[i], hole[j], board[a], board[b], board[c]]));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let (_score, cards) = best?;
+        return Some(LowHand::ace_to_five_five(&cards));
+    }
+
+    pub fn qualifies(&self, qualifier: Rank) -> bool {
+        self.rank == HandRank::HighCard
+            && self.cards.len() == 5
+            && self.cards.iter().all(|card| card.scoring_rank <= qualifier)
+    }
+
+    pub fn high_card(&self) -> Option<&Card> {
+        self.cards.first()
+    }
+}
+
+impl PartialEq for LowHand {
+    fn eq(&self, other: &LowHand) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for LowHand {}
+
+impl Ord for LowHand {
+    fn cmp(&self, other: &LowHand) -> Ordering {
+        other.score.cmp(&self.score)
+    }
+}
+
+impl PartialOrd for LowHand {
+    fn partial_cmp(&self, other: &LowHand) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for LowHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.rank, self.high_card()) {
+            (HandRank::HighCard, Some(card)) => write!(f, "{} -> {} Low", fmt_cards(&self.cards), card.rank),
+            _ => write!(f, "{} -> {}", fmt_cards(&self.cards), self.rank)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use Rank::*;
+    use HandRank::*;
+
+    fn ace_to_five(card_string: &str) -> LowHand {
+        let cards = CardVector::parse(card_string);
+        return LowHand::ace_to_five(&cards.iter().collect::<Vec<_>>());
+    }
+
+    fn deuce_to_seven(card_string: &str) -> LowHand {
+        let cards = CardVector::parse(card_string);
+        return LowHand::deuce_to_seven(&cards.iter().collect::<Vec<_>>());
+    }
+
+    fn ace_to_five_eight_or_better(card_string: &str) -> Option<LowHand> {
+        let cards = CardVector::parse(card_string);
+        LowHand::ace_to_five_qualified(&cards.iter().collect::<Vec<_>>(), Eight)
+    }
+
+    #[test]
+    fn test_wheel_is_best() {
+        let wheel = ace_to_five("5c 4d 3h 2s Ac");
+        assert_eq!(wheel.rank, HighCard);
+        assert_eq!(wheel.cards[0].scoring_rank, Five);
+        assert_eq!(wheel.cards[4].scoring_rank, LowAce);
+
+        assert_eq!(wheel.cmp(&ace_to_five("6c 4d 3h 2s Ac")), Ordering::Greater);
+        assert_eq!(wheel.cmp(&ace_to_five("5d 4c 3c 2c Ah")), Ordering::Equal);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least five cards")]
+    fn test_short_hand_rejected() {
+        ace_to_five("Ac 2d 3h 4s");
+    }
+
+    #[test]
+    fn test_straights_and_flushes_ignored() {
+        assert_eq!(ace_to_five("5c 4c 3c 2c Ac").rank, HighCard);
+        assert_eq!(ace_to_five("9c 8d 7h 6s 5c").rank, HighCard);
+    }
+
+    #[test]
+    fn test_ordering() {
+        let ordered = ["5c 4d 3h 2s Ac",
+                       "6c 4d 3h 2s Ac",
+                       "6c 5d 4h 3s 2c",
+                       "7c 6d 4h 3s 2c",
+                       "8c 4d 3h 2s Ac",
+                       "8c 7d 6h 5s 4c",
+                       "9c 4d 3h 2s Ac",
+                       "Kc Qd Jh Ts 8c",
+                       "Ac Ad 2h 3s 4c",
+                       "2c 2d 3h 4s 5c",
+                       "Kc Kd 2h 3s 4c",
+                       "Ac Ad 2h 2s 3c",
+                       "3c 3d 2h 2s Ac",
+                       "Ac Ad Ah 2s 3c",
+                       "Ac Ad Ah 2s 2c",
+                       "Ac Ad Ah As 2c"];
+
+        for pair in ordered.windows(2) {
+            assert_eq!(ace_to_five(pair[0]).cmp(&ace_to_five(pair[1])), Ordering::Greater,
+                       "{} should beat {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_razz_seven_cards() {
+        let hand = ace_to_five("Kc Kd 4h 2s Ac 4d 2h");
+        assert_eq!(hand.rank, OnePair);
+        assert_eq!(hand.cards[0].scoring_rank, Two);
+        assert_eq!(hand.cards[2].scoring_rank, King);
+
+        let hand = ace_to_five("Kc Qd 7h 2s Ac 4d 3h");
+        assert_eq!(hand.rank, HighCard);
+        assert_eq!(hand.score, ace_to_five("7h 4d 3h 2s Ac").score);
+    }
+
+    #[test]
+    fn test_eight_or_better() {
+        assert!(ace_to_five_eight_or_better("8c 7d 6h 5s 4c").is_some());
+        assert!(ace_to_five_eight_or_better("9c 4d 3h 2s Ac").is_none());
+        assert!(ace_to_five_eight_or_better("Ac Ad 2h 3s 4c").is_none());
+        assert!(ace_to_five_eight_or_better("Kc Qd 8h 2s Ac 4d 3h").is_some());
+        assert!(ace_to_five_eight_or_better("Kc Qd 9h 2s Ac 2d 3h").is_none());
+    }
+
+    #[test]
+    fn test_deuce_to_seven_categories() {
+        assert_eq!(deuce_to_seven("7c 5d 4h 3s 2c").rank, HighCard);
+        assert_eq!(deuce_to_seven("Ac 5d 4h 3s 2c").rank, HighCard);
+        assert_eq!(deuce_to_seven("6c 5d 4h 3s 2c").rank, Straight);
+        assert_eq!(deuce_to_seven("Ac Kd Qh Js Tc").rank, Straight);
+        assert_eq!(deuce_to_seven("7c 5c 4c 3c 2c").rank, Flush);
+        assert_eq!(deuce_to_seven("6c 5c 4c 3c 2c").rank, StraightFlush);
+        assert_eq!(deuce_to_seven("2c 2d 4h 3s 5c").rank, OnePair);
+    }
+
+    #[test]
+    fn test_deuce_to_seven_ordering() {
+        let ordered = ["7c 5d 4h 3s 2c",
+                       "7c 6d 4h 3s 2c",
+                       "7c 6d 5h 3s 2c",
+                       "7c 6d 5h 4s 2c",
+                       "8c 5d 4h 3s 2c",
+                       "8c 6d 5h 4s 3c",
+                       "9c 5d 4h 3s 2c",
+                       "Kc Qd Jh Ts 8c",
+                       "Ac 5d 4h 3s 2c",
+                       "Ac Kd Qh Js 9c",
+                       "2c 2d 5h 4s 3c",
+                       "Ac Ad Kh Qs Jc",
+                       "3c 3d 2h 2s 4c",
+                       "2c 2d 2h 3s 4c",
+                       "6c 5d 4h 3s 2c",
+                       "Ac Kd Qh Js Tc",
+                       "7c 5c 4c 3c 2c",
+                       "2c 2d 2h 3s 3c",
+                       "2c 2d 2h 2s 3c",
+                       "6c 5c 4c 3c 2c"];
+
+        for pair in ordered.windows(2) {
+            assert_eq!(deuce_to_seven(pair[0]).cmp(&deuce_to_seven(pair[1])), Ordering::Greater,
+                       "{} should beat {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_deuce_to_seven_ties() {
+        assert_eq!(deuce_to_seven("7c 5d 4h 3s 2c").cmp(&deuce_to_seven("7d 5c 4s 3h 2d")), Ordering::Equal);
+        assert_eq!(deuce_to_seven("Kc Kd 9h 5s 2c").cmp(&deuce_to_seven("Kh Ks 9c 5d 2h")), Ordering::Equal);
+        assert_eq!(deuce_to_seven("Kc Kd 9h 5s 2c").cmp(&deuce_to_seven("Kh Ks 9c 5d 3h")), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_deuce_to_seven_best_five() {
+        let hand = deuce_to_seven("7c 5d 4h 3s 2c 2d Ac");
+        assert_eq!(hand.rank, HighCard);
+        assert_eq!(hand.score, deuce_to_seven("7c 5d 4h 3s 2c").score);
+    }
+
+    #[test]
+    fn test_omaha_ace_to_five() {
+        let omaha_low = |hole: &str, board: &str| {
+            let hole = CardVector::parse(hole);
+            let board = CardVector::parse(board);
+            LowHand::omaha_ace_to_five(&hole.iter().collect::<Vec<_>>(), &board.iter().collect::<Vec<_>>(), Eight)
+        };
+
+        let hand = omaha_low("Ac 2d Kh Ks", "3c 4d 8h Qs Jc").unwrap();
+        assert_eq!(hand.score, ace_to_five("8h 4d 3c 2d Ac").score);
+
+        // Only one low card in the hole
+        assert!(omaha_low("Ac Kd Kh Ks", "2c 3d 4h 5s 6c").is_none());
+        // Only two low cards on the board
+        assert!(omaha_low("Ac 2d Kh Ks", "3c 4d Th Qs Jc").is_none());
+        // Counterfeited: the hole deuce pairs the board
+        assert!(omaha_low("Ac 2d Kh Ks", "2c 3d 9h Qs 4c").is_none());
+    }
+}
@@ -1,211 +1,626 @@
-#![feature(iterator_fold_self)]
-#![feature(drain_filter)]
-#![allow(dead_code)]
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+use std::path::Path;
+use std::time::Duration;
+
+use clap::{App, Arg, ArgMatches};
+
+use poker_rust::card::{Card, CardSet, CardVector, fmt_cards};
+use poker_rust::win_lose_split::WinLoseSplit;
+use poker_rust::pot_equity::PotEquity;
+use poker_rust::hand_rank_count::HandRankCount;
+use poker_rust::game::Game;
+use poker_rust::hold_em::{game_odds, game_deal_count, has_random_pockets, default_thread_count};
+use poker_rust::monte_carlo::{game_monte_carlo, MonteCarloOptions};
+use poker_rust::hi_lo::{hi_lo_odds, hi_lo_monte_carlo};
+use poker_rust::stud::{StudHand, stud_odds, stud_monte_carlo, stud_deal_count, validate_stud_hands};
+use poker_rust::draw::{DrawGame, DrawHand, draw_odds, draw_monte_carlo, draw_deal_count, validate_draw_hands};
+use poker_rust::discard::advise_discard;
+use poker_rust::wild::WildCards;
+use poker_rust::range::HandRange;
+use poker_rust::range_equity::{range_odds, range_monte_carlo, range_deal_count};
+use poker_rust::preflop_table::PreflopTable;
+
+/// Stud, draw, range and random pocket deals are enumerated up to this count
+/// and sampled beyond it.
+const MAX_DEALS: u64 = 10_000_000;
+
+/// Empty pockets are dealt at random.
+fn fmt_pocket(pocket: &[Card]) -> String {
+    if pocket.is_empty() { "random".to_string() } else { fmt_cards(pocket) }
+}
 
-extern crate strum;
-#[macro_use] extern crate strum_macros;
+fn print_board(board: &[Card], dead: &[Card]) {
+    if !board.is_empty() {
+        println!("Board: {}", fmt_cards(board));
+    }
+    if !dead.is_empty() {
+        println!("Dead: {}", fmt_cards(dead));
+    }
+}
 
-use std::cmp::Ordering;
-use itertools::Itertools;
-use clap::{App, Arg};
-use strum::IntoEnumIterator;
+fn print_results(pockets: &[Vec<Card>], board: &[Card], dead: &[Card], results: &[WinLoseSplit], hand_rank_counts: &[HandRankCount], show_error: bool) {
+    print_board(board, dead);
 
-mod card;
-use card::{Card, CardVector, fmt_cards};
+    if pockets.len() >= 2 {
+        for i in 0..results.len() {
+            let p = &pockets[i];
+            let r = results[i];
+            if show_error {
+                let (low, high) = r.win_confidence_interval(1.96);
+                println!("- {} - {} (95% CI {:.2}% - {:.2}%)", fmt_pocket(p), r, low, high);
+            } else {
+                println!("- {} - {}", fmt_pocket(p), r);
+            }
+        }
+        println!();
+    }
 
-mod deck;
-use deck::{make_deck, make_shuffled_deck};
+    print_hand_rank_counts(pockets, hand_rank_counts);
+}
 
-mod poker_hand;
-use poker_hand::{PokerHand, HandRank};
+fn print_pot_equity(pockets: &[Vec<Card>], board: &[Card], dead: &[Card], results: &[PotEquity], hand_rank_counts: &[HandRankCount]) {
+    print_board(board, dead);
 
-mod win_lose_split;
-use win_lose_split::WinLoseSplit;
+    if let Some(result) = results.first() {
+        println!("Qualifying low: {:.2}%", result.low_pct());
+    }
 
-struct HandRankCount(Vec<usize>);
-impl HandRankCount {
-    pub fn new() -> HandRankCount {
-        HandRankCount(vec![0; 1 + HandRank:: StraightFlush as usize])
+    for (pocket, result) in pockets.iter().zip(results.iter()) {
+        println!("- {} - {}", fmt_pocket(pocket), result);
     }
+    println!();
+
+    print_hand_rank_counts(pockets, hand_rank_counts);
+}
 
-    pub fn inc(&mut self, rank: HandRank) {
-        self.0[rank as usize] += 1;
+fn print_hand_rank_counts(pockets: &[Vec<Card>], hand_rank_counts: &[HandRankCount]) {
+    for i in 0..hand_rank_counts.len() {
+        println!("{}", fmt_pocket(&pockets[i]));
+        println!("{}", hand_rank_counts[i]);
     }
 }
 
-impl std::ops::Deref for HandRankCount {
-    type Target = Vec<usize>;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+/// Heads-up preflop hold'em results from the `--table` file, when one is
+/// given and covers the deal.
+fn table_lookup(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], wild: &WildCards, matches: &ArgMatches) -> Option<Vec<WinLoseSplit>> {
+    let path = matches.value_of("table")?;
+    if game != Game::HoldEm || pockets.len() != 2 || !board.is_empty() || !dead.is_empty() || !wild.is_empty() {
+        return None;
     }
+
+    let table = PreflopTable::load(Path::new(path)).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        std::process::exit(1);
+    });
+    Some(vec![table.lookup(&pockets[0], &pockets[1])?, table.lookup(&pockets[1], &pockets[0])?])
 }
 
-impl std::fmt::Display for HandRankCount {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let total = self.0.iter()
-            .fold(0, |acc, current| current + acc) as f32;
-        
-        for rank in HandRank::iter() {
-            let count = self.0[rank as usize] as f32;
-            let p = 100.0 * count / total;
-            write!(f, "{:14} - {:5.2} %%\n", rank.to_string(), p)?;
+fn enumerate_deals(game: Game, pockets: Vec<Vec<Card>>, board: &[Card], dead: &[Card], wild: &WildCards, threads: usize, matches: &ArgMatches) {
+    if let Some(results) = table_lookup(game, &pockets, board, dead, wild, matches) {
+        print_results(&pockets, board, dead, &results, &[], false);
+        return;
+    }
+
+    let too_many_deals = has_random_pockets(&pockets)
+        && game_deal_count(game, &pockets, board, dead, wild).is_none_or(|count| count > MAX_DEALS);
+    if matches.is_present("sample") || too_many_deals {
+        simulate_deals(game, pockets, board, dead, wild, &parse_monte_carlo_options(matches));
+        return;
+    }
+
+    let mut hand_rank_counts = Vec::new();
+    for _i in 0..pockets.len() {
+        hand_rank_counts.push(HandRankCount::new());
+    }
+
+    if game.is_hi_lo() {
+        let results = hi_lo_odds(game, &pockets, board, dead, &mut hand_rank_counts, threads);
+        print_pot_equity(&pockets, board, dead, &results, &hand_rank_counts);
+    } else {
+        let results = game_odds(game, &pockets, board, dead, wild, &mut hand_rank_counts, threads);
+        print_results(&pockets, board, dead, &results, &hand_rank_counts, false);
+    }
+}
+
+fn simulate_deals(game: Game, pockets: Vec<Vec<Card>>, board: &[Card], dead: &[Card], wild: &WildCards, options: &MonteCarloOptions) {
+    let mut hand_rank_counts = Vec::new();
+    for _i in 0..pockets.len() {
+        hand_rank_counts.push(HandRankCount::new());
+    }
+
+    if game.is_hi_lo() {
+        let results = hi_lo_monte_carlo(game, &pockets, board, dead, &mut hand_rank_counts, options);
+        if let Some(result) = results.first() {
+            println!("Trials: {}", result.pots);
+        }
+        print_pot_equity(&pockets, board, dead, &results, &hand_rank_counts);
+    } else {
+        let results = game_monte_carlo(game, &pockets, board, dead, wild, &mut hand_rank_counts, options);
+        if let Some(result) = results.first() {
+            println!("Trials: {}", result.total());
         }
-        Ok(())
+        print_results(&pockets, board, dead, &results, &hand_rank_counts, true);
     }
 }
 
-fn deal(cards: &mut Vec<Card>, n: usize) {
-    let mut pockets = Vec::new();
+fn print_stud_equity(hands: &[StudHand], dead: &[Card], results: &[PotEquity], hand_rank_counts: &[HandRankCount], hi_lo: bool) {
+    if !dead.is_empty() {
+        println!("Dead: {}", fmt_cards(dead));
+    }
 
-    for card_index in 0..2 {
-        for pocket_index in 0..n {
-            if card_index == 0 {
-                pockets.push(Vec::new());
-            }
+    if hi_lo {
+        if let Some(result) = results.first() {
+            println!("Qualifying low: {:.2}%", result.low_pct());
+        }
+    }
 
-            if let Some(top) = cards.pop() {
-                pockets[pocket_index].push(top);
-            }
+    for (hand, result) in hands.iter().zip(results.iter()) {
+        if hi_lo {
+            println!("- {} - {}", hand, result);
+        } else {
+            println!("- {} - {:.2}% equity - {}", hand, result.equity_pct(), result.high);
         }
     }
+    println!();
+
+    for (hand, count) in hands.iter().zip(hand_rank_counts.iter()) {
+        println!("{}", hand);
+        println!("{}", count);
+    }
+}
+
+fn stud_deals(matches: &ArgMatches) {
+    let hands = matches.values_of("hand")
+        .map(|hand_strings| hand_strings.map(|str| str.parse::<StudHand>()).collect::<Result<Vec<_>, _>>())
+        .unwrap_or_else(|| Ok(Vec::new()))
+        .unwrap_or_else(|err| {
+            eprintln!("Invalid stud hand: {}", err);
+            std::process::exit(1);
+        });
+    let dead = parse_dead(matches);
+    let hi_lo = matches.is_present("hi-lo");
+    let wild = parse_wild_cards(matches);
+
+    if hi_lo && !wild.is_empty() {
+        eprintln!("Wild cards are not supported for Stud Hi/Lo");
+        std::process::exit(1);
+    }
 
-    let mut burns = Vec::new();
-    let mut board = Vec::new();
+    if let Err(err) = validate_stud_hands(&hands, &dead, &wild) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 
-    if let Some(burn) = cards.pop() { burns.push(burn); }
-    if let Some(card) = cards.pop() { board.push(card); }
-    if let Some(card) = cards.pop() { board.push(card); }
-    if let Some(card) = cards.pop() { board.push(card); }
-    if let Some(burn) = cards.pop() { burns.push(burn); }
-    if let Some(card) = cards.pop() { board.push(card); }
-    if let Some(burn) = cards.pop() { burns.push(burn); }
-    if let Some(card) = cards.pop() { board.push(card); }
+    let mut hand_rank_counts = HandRankCount::for_players(hands.len());
+    let enumerate = !matches.is_present("sample")
+        && stud_deal_count(&hands, &dead, &wild).is_some_and(|count| count <= MAX_DEALS);
 
-    println!("Board: {}", fmt_cards(&board));
+    let results = if enumerate {
+        stud_odds(&hands, &dead, &wild, hi_lo, &mut hand_rank_counts, parse_threads(matches))
+    } else {
+        let results = stud_monte_carlo(&hands, &dead, &wild, hi_lo, &mut hand_rank_counts, &parse_monte_carlo_options(matches));
+        if let Some(result) = results.first() {
+            println!("Trials: {}", result.pots);
+        }
+        results
+    };
 
-    let mut evals = Vec::new();
-    for pocket in &pockets {
-        let mut cards = pocket.to_vec();
-        cards.extend(board.to_vec());
+    print_stud_equity(&hands, &dead, &results, &hand_rank_counts, hi_lo);
+}
 
-        let poker_hand = PokerHand::build(&cards.iter().collect::<Vec<_>>(), &None);
-        evals.push((pocket, poker_hand));
+fn draw_deals(matches: &ArgMatches) {
+    let game: DrawGame = matches.value_of_t("game").unwrap_or_else(|e| e.exit());
+    let hands = matches.values_of("hand")
+        .map(|hand_strings| hand_strings.map(|str| str.parse::<DrawHand>()).collect::<Result<Vec<_>, _>>())
+        .unwrap_or_else(|| Ok(Vec::new()))
+        .unwrap_or_else(|err| {
+            eprintln!("Invalid draw hand: {}", err);
+            std::process::exit(1);
+        });
+    let dead = parse_dead(matches);
+
+    if let Err(err) = validate_draw_hands(game, &hands, &dead) {
+        eprintln!("{}", err);
+        std::process::exit(1);
     }
 
-    evals.sort_by(|a, b| a.1.cmp(&b.1));
-    evals.reverse();
+    let enumerate = !matches.is_present("sample")
+        && draw_deal_count(game, &hands, &dead).is_some_and(|count| count <= MAX_DEALS);
 
-    for eval in evals {
-        let (pocket, poker_hand) = eval;
-        println!("Pocket: {} -> {}", fmt_cards(&pocket), poker_hand);
+    let results = if enumerate {
+        draw_odds(game, &hands, &dead, parse_threads(matches))
+    } else {
+        let results = draw_monte_carlo(game, &hands, &dead, &parse_monte_carlo_options(matches));
+        if let Some(result) = results.first() {
+            println!("Trials: {}", result.total());
+        }
+        results
+    };
+
+    if !dead.is_empty() {
+        println!("Dead: {}", fmt_cards(&dead));
+    }
+    for (hand, result) in hands.iter().zip(results.iter()) {
+        println!("- {} draws {} - {}", fmt_cards(&hand.kept), hand.draw_count(game), result);
     }
 }
 
-fn find_winners(pockets: &Vec<Vec<Card>>, board: &Vec<&Card>, hand_rank_counts: &mut Vec<HandRankCount>) -> Vec<usize> {
-    let mut vec = Vec::new();
-    
-    let mut best_hand = None;
-    for (index, pocket) in pockets.iter().enumerate() {
-        let mut current = board.to_vec();
-        current.extend(pocket);
-        let hand = PokerHand::build(&current, &None);
+fn discard_advice(matches: &ArgMatches) {
+    let hand = parse_cards(matches.value_of("hand").unwrap_or(""));
+    let opponent = matches.value_of("opponent").unwrap_or("/5").parse::<DrawHand>().unwrap_or_else(|err| {
+        eprintln!("Invalid opponent: {}", err);
+        std::process::exit(1);
+    });
+    let dead = parse_dead(matches);
+
+    let hands = vec![DrawHand::new(hand.clone(), 0), opponent.clone()];
+    if let Err(err) = validate_draw_hands(DrawGame::FiveCardDraw, &hands, &dead) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 
-        hand_rank_counts[index].inc(hand.rank);
-        
-        if let Some(max) = &best_hand {
-            match hand.cmp(&max) {
-                Ordering::Equal => vec.push(index),
-                Ordering::Greater => {
-                    vec.clear();
-                    vec.push(index);
-                    best_hand = Some(hand)
-                },
-                Ordering::Less => {}
+    let holds = advise_discard(&hand, &opponent, &dead, &parse_monte_carlo_options(matches), parse_threads(matches));
+    let shown = if matches.is_present("all") { holds.len() } else { 5 };
+
+    println!("Hand: {}", fmt_cards(&hand));
+    println!("Opponent: {}", opponent);
+    if let Some(best) = holds.first() {
+        println!("Best: {}", best);
+    }
+    println!();
+
+    for hold in holds.iter().take(shown) {
+        println!("- {}", hold);
+        println!("{}", hold.hand_rank_count);
+    }
+}
+
+fn parse_ranges(matches: &ArgMatches) -> Vec<HandRange> {
+    matches.values_of("range")
+        .map(|range_strings| range_strings.map(|str| str.parse::<HandRange>()).collect::<Result<Vec<_>, _>>())
+        .unwrap_or_else(|| Ok(Vec::new()))
+        .unwrap_or_else(|err| {
+            eprintln!("Invalid range: {}", err);
+            std::process::exit(1);
+        })
+}
+
+fn range_combos(matches: &ArgMatches) {
+    let ranges = parse_ranges(matches);
+    let board = parse_board(matches);
+    let dead = parse_dead(matches);
+    validate_board(Game::HoldEm, &board);
+    validate_dead(Game::HoldEm, &[], &board, &dead);
+    let known = board.iter().chain(dead.iter()).collect::<CardSet>();
+
+    let range_strings = matches.values_of("range").into_iter().flatten().collect::<Vec<_>>();
+    if ranges.len() < 2 {
+        for (range_string, range) in range_strings.iter().zip(ranges.iter()) {
+            let live = range.without(&known);
+            println!("{} - {} combos ({:.2} weighted)", range_string, live.len(), live.weighted_len());
+        }
+        return;
+    }
+
+    for (range_string, range) in range_strings.iter().zip(ranges.iter()) {
+        if range.without(&known).weighted_len() <= 0.0 {
+            eprintln!("No live combos in {}", range_string);
+            std::process::exit(1);
+        }
+    }
+
+    let enumerate = !matches.is_present("sample")
+        && range_deal_count(&ranges, &board, &dead).is_some_and(|count| count <= MAX_DEALS);
+
+    let results = if enumerate {
+        range_odds(&ranges, &board, &dead, parse_threads(matches))
+    } else {
+        let results = range_monte_carlo(&ranges, &board, &dead, &parse_monte_carlo_options(matches));
+        println!("Trials: {}", results.seats[0].weight);
+        results
+    };
+
+    if results.seats.iter().any(|seat| seat.weight <= 0.0) {
+        eprintln!("No deal is possible between these ranges");
+        std::process::exit(1);
+    }
+
+    if !board.is_empty() {
+        println!("Board: {}", fmt_cards(&board));
+    }
+    for ((range_string, range), result) in range_strings.iter().zip(ranges.iter()).zip(results.seats.iter()) {
+        let live = range.without(&known);
+        println!("- {} - {} combos ({:.2} weighted) - {:.2}% equity", range_string, live.len(), live.weighted_len(), result.equity_pct());
+    }
+
+    if matches.is_present("combos") {
+        println!();
+        for (combo, result) in results.hero_combos.iter().filter(|(_combo, result)| result.weight > 0.0) {
+            println!("- {} - {:.2}% equity", combo, result.equity_pct());
+        }
+    }
+}
+
+fn generate_preflop_table(matches: &ArgMatches) {
+    let path = matches.value_of("file").unwrap();
+    let table = PreflopTable::generate(parse_threads(matches), |done, total| {
+        if done % 10 == 0 || done == total {
+            eprint!("\r{}/{} matchups", done, total);
+        }
+    });
+    eprintln!();
+
+    table.save(Path::new(path)).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        std::process::exit(1);
+    });
+    println!("Wrote {} matchups to {}", table.len(), path);
+}
+
+fn table_arg() -> Arg<'static> {
+    Arg::new("table")
+        .long("table")
+        .help("Heads-up preflop equity table written by the preflop-table command")
+        .takes_value(true)
+}
+
+fn dead_arg() -> Arg<'static> {
+    Arg::new("dead")
+        .short('d')
+        .long("dead")
+        .help("Folded or discarded cards that can not be dealt")
+        .takes_value(true)
+}
+
+fn sample_arg() -> Arg<'static> {
+    Arg::new("sample")
+        .long("sample")
+        .help("Always sample instead of enumerating")
+}
+
+fn board_arg() -> Arg<'static> {
+    Arg::new("board")
+        .short('b')
+        .long("board")
+        .help("Partial Board")
+        .takes_value(true)
+}
+
+fn pocket_arg() -> Arg<'static> {
+    Arg::new("pocket")
+        .takes_value(true)
+        .multiple_values(true)
+        .index(1)
+        .min_values(1)
+        .max_values(9)
+        .help("Pocket cards, or xx for a random hand")
+        .validator(parse_pocket)
+}
+
+fn game_arg() -> Arg<'static> {
+    Arg::new("game")
+        .short('g')
+        .long("game")
+        .help("Game to simulate")
+        .takes_value(true)
+        .possible_values(["holdem", "omaha", "omaha8", "shortdeck", "shortdeck-trips"])
+        .default_value("holdem")
+}
+
+fn parse_game(matches: &ArgMatches) -> Game {
+    matches.value_of_t("game").unwrap_or_else(|e| e.exit())
+}
+
+/// Each pocket must suit the game and no card may be dealt twice, whether to
+/// two pockets or to a pocket and the board. Jokers are all alike, so they
+/// are counted by `validate_jokers` instead.
+fn validate_pockets(game: Game, pockets: &[Vec<Card>], board: &[Card]) {
+    let mut dealt = CardSet::new();
+    for cards in pockets.iter().map(|pocket| &pocket[..]).chain(std::iter::once(board)) {
+        let cards = cards.iter().filter(|card| !Card::is_joker(card)).cloned().collect::<Vec<_>>();
+        let card_set = CardSet::from(&cards[..]);
+        if card_set.len() != cards.len() || !card_set.is_disjoint(&dealt) {
+            eprintln!("Cards dealt more than once: {}", fmt_cards(&cards));
+            std::process::exit(1);
+        }
+        dealt |= card_set;
+    }
+
+    for pocket in pockets.iter().filter(|pocket| !pocket.is_empty()) {
+        if !game.is_valid_pocket(pocket) {
+            let (min, max) = game.pocket_sizes();
+            if pocket.len() < min || pocket.len() > max {
+                eprintln!("Invalid pocket {} for {}: expected {} to {} cards", fmt_cards(pocket), game, min, max);
+            } else {
+                eprintln!("Invalid pocket {} for {}: card not in the deck", fmt_cards(pocket), game);
             }
-        } else {
-            vec.push(index);
-            best_hand = Some(hand);
+            std::process::exit(1);
         }
     }
-    
-    return vec;
 }
 
-fn remove_card(deck: &mut Vec<Card>, card: &Card) {
-    if let Some(pos) = deck.iter().position(|x| *x == *card) {
-        deck.remove(pos);
+fn validate_board(game: Game, board: &[Card]) {
+    if board.len() > 5 || CardSet::from(board).len() != board.len() || !board.iter().all(|card| game.is_valid_card(card)) {
+        eprintln!("Invalid board {} for {}", fmt_cards(board), game);
+        std::process::exit(1);
     }
 }
 
-fn remove_all_cards(deck: &mut Vec<Card>, cards: &[Card]) {
-    for card in cards {
-        remove_card(deck, card);
+/// Dead cards must be in the game's deck and not already dealt.
+fn validate_dead(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card]) {
+    let known = pockets.iter().flatten().chain(board.iter()).collect::<CardSet>();
+    let dead_set = CardSet::from(dead);
+    if dead_set.len() != dead.len() || !dead_set.is_disjoint(&known) || !dead.iter().all(|card| game.is_valid_card(card) && !Card::is_joker(card)) {
+        eprintln!("Invalid dead cards {} for {}", fmt_cards(dead), game);
+        std::process::exit(1);
     }
 }
 
+/// Parses cards such as "Ac Kd" or "AcKd", exiting on anything else.
+fn parse_cards(cards_string: &str) -> Vec<Card> {
+    cards_string.parse::<CardVector>()
+        .map(|cards| cards.0)
+        .unwrap_or_else(|err| {
+            eprintln!("{}: {}", err, cards_string);
+            std::process::exit(1);
+        })
+}
 
-fn hold_em_odds(pockets: &Vec<Vec<Card>>, board: &Vec<Card>, hand_rank_counts: &mut Vec<HandRankCount>) -> Vec<WinLoseSplit> {
-    let mut deck = make_deck();
-    for pocket in pockets.iter() {
-        remove_all_cards(&mut deck, pocket);
+fn parse_board(matches: &ArgMatches) -> Vec<Card> {
+    parse_cards(matches.value_of("board").unwrap_or(""))
+}
+
+fn parse_dead(matches: &ArgMatches) -> Vec<Card> {
+    parse_cards(matches.value_of("dead").unwrap_or(""))
+}
+
+/// `xx` or `random` is an empty pocket, dealt at random.
+fn parse_pocket(pocket: &str) -> Result<Vec<Card>, &'static str> {
+    match pocket.to_ascii_lowercase().as_str() {
+        "xx" | "random" => Ok(Vec::new()),
+        _ => pocket.parse::<CardVector>().map(|cards| cards.0)
     }
-    remove_all_cards(&mut deck, board); 
+}
 
-    let mut results = vec![WinLoseSplit::new(); pockets.len()];
+/// The pockets have already been checked by the `pocket_arg` validator.
+fn parse_pockets(matches: &ArgMatches) -> Option<Vec<Vec<Card>>> {
+    matches.values_of("pocket").map(|pocket_strings| {
+        pocket_strings
+            .map(|str| parse_pocket(str).unwrap())
+            .collect::<Vec<Vec<Card>>>()
+    })
+}
 
-    let n = 5 - board.len();
-    for combination in deck.iter().combinations(n) {
-        let complete_board = board.iter().chain(combination).collect::<Vec<_>>();
-        
-        let winners = find_winners(pockets, &complete_board, hand_rank_counts);
-        for index in 0..results.len() { 
-            if winners.contains(&index) {
-                if winners.len() == 1 {
-                    results[index].wins += 1;
-                } else {
-                    results[index].splits += 1;
-                }
-            } else {
-                results[index].losses += 1;
-            }
-        }
+fn jokers_arg() -> Arg<'static> {
+    Arg::new("jokers")
+        .long("jokers")
+        .help("Number of wild jokers added to the deck, written ?? in hands")
+        .takes_value(true)
+}
+
+fn wild_arg() -> Arg<'static> {
+    Arg::new("wild")
+        .long("wild")
+        .help("Wild card rules")
+        .takes_value(true)
+        .multiple_occurrences(true)
+        .possible_values(["deuces", "one-eyed-jacks", "suicide-king", "baseball", "low-hole", "follow-the-queen"])
+}
+
+fn wild_cards_arg() -> Arg<'static> {
+    Arg::new("wild-cards")
+        .long("wild-cards")
+        .help("Specific wild cards, e.g. \"7c 7d\"")
+        .takes_value(true)
+}
+
+fn parse_wild(game: Game, matches: &ArgMatches) -> WildCards {
+    let wild = parse_wild_cards(matches);
+    if !wild.is_empty() && (game.is_hi_lo() || game.is_short_deck()) {
+        eprintln!("Wild cards are not supported for {}", game);
+        std::process::exit(1);
+    }
+    if wild.has_up_card_rules() {
+        eprintln!("Follow the Queen is only played in stud");
+        std::process::exit(1);
     }
-    
-    return results;
+    return wild;
 }
 
-fn random_deals() {
-    for _n in 0..100 {
-        let mut deck = make_shuffled_deck();
-        deal(&mut deck, 8);
+fn parse_wild_cards(matches: &ArgMatches) -> WildCards {
+    let mut wild = WildCards::new();
+
+    if matches.is_present("jokers") {
+        wild = wild.with_jokers(matches.value_of_t("jokers").unwrap_or_else(|e| e.exit()));
+    }
+
+    for name in matches.values_of("wild").into_iter().flatten() {
+        wild = wild.with_named_rule(name).unwrap_or_else(|err| {
+            eprintln!("{}: {}", err, name);
+            std::process::exit(1);
+        });
     }
+
+    if let Some(cards) = matches.value_of("wild-cards") {
+        wild = wild.with_cards(&parse_cards(cards));
+    }
+
+    return wild;
 }
 
-fn enumerate_deals(pockets: Vec<Vec<Card>>, board: &Vec<Card>) {
-    let mut hand_rank_counts = Vec::new();
-    for _i in 0..pockets.len() {
-        hand_rank_counts.push(HandRankCount::new());
+fn validate_jokers(wild: &WildCards, pockets: &[Vec<Card>], board: &[Card]) {
+    let dealt = pockets.iter().flatten().chain(board.iter()).filter(|card| Card::is_joker(card)).count();
+    if dealt > wild.jokers {
+        eprintln!("{} jokers dealt but only {} in the deck", dealt, wild.jokers);
+        std::process::exit(1);
+    }
+    if wild.jokers > 1 && has_random_pockets(pockets) {
+        eprintln!("Random pockets are not supported with more than one joker");
+        std::process::exit(1);
     }
+}
+
+fn threads_arg() -> Arg<'static> {
+    Arg::new("threads")
+        .short('j')
+        .long("threads")
+        .help("Number of enumeration threads (defaults to the number of cores)")
+        .takes_value(true)
+}
 
-    let results = hold_em_odds(&pockets, board, &mut hand_rank_counts);
+fn trials_arg() -> Arg<'static> {
+    Arg::new("trials")
+        .short('n')
+        .long("trials")
+        .help("Number of random run-outs")
+        .takes_value(true)
+        .default_value("100000")
+        .validator(|trials: &str| match trials.parse::<usize>() {
+            Ok(0) => Err("must be at least 1".to_string()),
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.to_string())
+        })
+}
 
-    if board.len() > 0 {
-        println!("Board: {}", fmt_cards(&board));
+fn time_arg() -> Arg<'static> {
+    Arg::new("time")
+        .short('t')
+        .long("time")
+        .help("Time budget in seconds")
+        .takes_value(true)
+}
+
+fn seed_arg() -> Arg<'static> {
+    Arg::new("seed")
+        .short('s')
+        .long("seed")
+        .help("Random seed for reproducible runs")
+        .takes_value(true)
+}
+
+fn parse_threads(matches: &ArgMatches) -> usize {
+    if matches.is_present("threads") {
+        matches.value_of_t("threads").unwrap_or_else(|e| e.exit())
+    } else {
+        default_thread_count()
     }
+}
 
-    if pockets.len() >= 2 {
-        for i in 0..results.len() {
-            let p = &pockets[i];
-            let r = results[i];
-            println!("- {} - {}", fmt_cards(&p), r);
-        }
-        println!("");
+fn parse_monte_carlo_options(matches: &ArgMatches) -> MonteCarloOptions {
+    let trials = matches.value_of_t("trials").unwrap_or_else(|e| e.exit());
+    let mut options = MonteCarloOptions::new(trials);
+
+    if matches.is_present("time") {
+        let seconds: f64 = matches.value_of_t("time").unwrap_or_else(|e| e.exit());
+        options = options.with_time_limit(Duration::from_secs_f64(seconds));
     }
 
-    for i in 0..hand_rank_counts.len() {
-        println!("{}", fmt_cards(&pockets[i]));
-        println!("{}", hand_rank_counts[i]);
+    if matches.is_present("seed") {
+        options = options.with_seed(matches.value_of_t("seed").unwrap_or_else(|e| e.exit()));
     }
+
+    return options;
 }
 
 fn main() {
@@ -213,34 +628,161 @@ fn main() {
         .version("1.0")
         .author("Chris Bowron <cwbowron@gmail.com>")
         .about("Calculate poker odds")
-        .arg(Arg::new("board")
-             .short('b')
-             .long("board")
-             .about("Partial Board")
-             .takes_value(true))
-        .arg(Arg::new("pocket")
-             .takes_value(true)
-             .multiple(true)
-             .index(1)
-             .min_values(1)
-             .max_values(9)
-             .about("Pocket cards"))
+        .arg(board_arg())
+        .arg(pocket_arg())
+        .arg(game_arg())
+        .arg(jokers_arg())
+        .arg(wild_arg())
+        .arg(wild_cards_arg())
+        .arg(dead_arg())
+        .arg(table_arg())
+        .arg(threads_arg())
+        .arg(sample_arg())
+        .arg(trials_arg())
+        .arg(time_arg())
+        .arg(seed_arg())
         .subcommand(App::new("montecarlo")
-                    .about("Monte Carlo Texas Hold 'em Simulation"))
+                    .about("Monte Carlo Texas Hold 'em Simulation")
+                    .arg(board_arg())
+                    .arg(pocket_arg())
+                    .arg(game_arg())
+                    .arg(jokers_arg())
+                    .arg(wild_arg())
+                    .arg(wild_cards_arg())
+                    .arg(dead_arg())
+                    .arg(trials_arg())
+                    .arg(time_arg())
+                    .arg(seed_arg()))
+        .subcommand(App::new("stud")
+                    .about("Seven Card Stud equity, enumerated when feasible and sampled otherwise")
+                    .arg(Arg::new("hand")
+                         .takes_value(true)
+                         .multiple_values(true)
+                         .index(1)
+                         .min_values(1)
+                         .max_values(8)
+                         .help("Down and up cards for each player, e.g. AcKd/Qh"))
+                    .arg(dead_arg())
+                    .arg(Arg::new("hi-lo")
+                         .long("hi-lo")
+                         .help("Split the pot with the best eight-or-better low"))
+                    .arg(jokers_arg())
+                    .arg(wild_arg())
+                    .arg(wild_cards_arg())
+                    .arg(sample_arg())
+                    .arg(threads_arg())
+                    .arg(trials_arg())
+                    .arg(time_arg())
+                    .arg(seed_arg()))
+        .subcommand(App::new("draw")
+                    .about("Draw game equity for the next draw, enumerated when feasible and sampled otherwise")
+                    .arg(Arg::new("hand")
+                         .takes_value(true)
+                         .multiple_values(true)
+                         .index(1)
+                         .min_values(1)
+                         .max_values(8)
+                         .help("Kept cards for each player, optionally with the number drawn, e.g. 7c5d4h/2"))
+                    .arg(Arg::new("game")
+                         .short('g')
+                         .long("game")
+                         .help("Draw game to simulate")
+                         .takes_value(true)
+                         .possible_values(["draw", "2-7", "badugi"])
+                         .default_value("draw"))
+                    .arg(dead_arg())
+                    .arg(sample_arg())
+                    .arg(threads_arg())
+                    .arg(trials_arg())
+                    .arg(time_arg())
+                    .arg(seed_arg()))
+        .subcommand(App::new("range")
+                    .about("Combo counts and equity between hand ranges such as \"QQ+, AKs, A5s-A2s, KTo+\"")
+                    .arg(Arg::new("range")
+                         .takes_value(true)
+                         .multiple_values(true)
+                         .index(1)
+                         .min_values(1)
+                         .max_values(9)
+                         .help("Hand ranges, with optional weights, e.g. \"QQ+, AKs:0.5\""))
+                    .arg(board_arg())
+                    .arg(dead_arg())
+                    .arg(Arg::new("combos")
+                         .long("combos")
+                         .help("Show the equity of each of the first range's combos"))
+                    .arg(sample_arg())
+                    .arg(threads_arg())
+                    .arg(trials_arg())
+                    .arg(time_arg())
+                    .arg(seed_arg()))
+        .subcommand(App::new("discard")
+                    .about("Five card draw discard advice: tries every hold and recommends the best")
+                    .arg(Arg::new("hand")
+                         .takes_value(true)
+                         .index(1)
+                         .required(true)
+                         .help("Five card hand"))
+                    .arg(Arg::new("opponent")
+                         .short('o')
+                         .long("opponent")
+                         .help("Opponent's kept cards and draw count, e.g. KcKd/3 (defaults to a random hand, /5)")
+                         .takes_value(true))
+                    .arg(Arg::new("all")
+                         .long("all")
+                         .help("Show every hold instead of the best five"))
+                    .arg(dead_arg())
+                    .arg(threads_arg())
+                    .arg(trials_arg())
+                    .arg(time_arg())
+                    .arg(seed_arg()))
+        .subcommand(App::new("preflop-table")
+                    .about("Enumerates every heads-up preflop hold'em matchup into a table for --table, which takes some hours")
+                    .arg(Arg::new("file")
+                         .takes_value(true)
+                         .index(1)
+                         .required(true)
+                         .help("Table file to write"))
+                    .arg(threads_arg()))
         .get_matches();
              
-    if matches.is_present("montecarlo") {
-        random_deals();
+    if let Some(stud_matches) = matches.subcommand_matches("stud") {
+        stud_deals(stud_matches);
+    } else if let Some(draw_matches) = matches.subcommand_matches("draw") {
+        draw_deals(draw_matches);
+    } else if let Some(range_matches) = matches.subcommand_matches("range") {
+        range_combos(range_matches);
+    } else if let Some(discard_matches) = matches.subcommand_matches("discard") {
+        discard_advice(discard_matches);
+    } else if let Some(table_matches) = matches.subcommand_matches("preflop-table") {
+        generate_preflop_table(table_matches);
+    } else if let Some(montecarlo_matches) = matches.subcommand_matches("montecarlo") {
+        let board = parse_board(montecarlo_matches);
+        let dead = parse_dead(montecarlo_matches);
+        let options = parse_monte_carlo_options(montecarlo_matches);
+
+        let game = parse_game(montecarlo_matches);
+        let wild = parse_wild(game, montecarlo_matches);
+
+        if let Some(pockets) = parse_pockets(montecarlo_matches) {
+            validate_pockets(game, &pockets, &board);
+            validate_board(game, &board);
+            validate_jokers(&wild, &pockets, &board);
+            validate_dead(game, &pockets, &board, &dead);
+            simulate_deals(game, pockets, &board, &dead, &wild, &options);
+        }
     } else {
-        let board_string = matches.value_of("board").unwrap_or("");
-        let board = CardVector::parse(board_string);
-        
-        if let Some(pocket_strings) = matches.values_of("pocket") {
-            let pockets  = pocket_strings
-                .map(|str| CardVector::parse(str).to_vec())
-                .collect::<Vec<Vec<Card>>>();
-            
-            enumerate_deals(pockets, &board);
+        let board = parse_board(&matches);
+        let dead = parse_dead(&matches);
+
+        let game = parse_game(&matches);
+        let wild = parse_wild(game, &matches);
+
+        if let Some(pockets) = parse_pockets(&matches) {
+            validate_pockets(game, &pockets, &board);
+            validate_board(game, &board);
+            validate_jokers(&wild, &pockets, &board);
+            validate_dead(game, &pockets, &board, &dead);
+            enumerate_deals(game, pockets, &board, &dead, &wild, parse_threads(&matches), &matches);
         }
     }
 }
@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use super::card::Card;
+use super::win_lose_split::WinLoseSplit;
+use super::hand_rank_count::HandRankCount;
+use super::hold_em::{find_game_winners, record_winners, game_deck, has_random_pockets, random_pocket_deal, fill_random_pockets};
+use super::deal::for_each_random_deal;
+use super::game::Game;
+use super::wild::WildCards;
+
+pub struct MonteCarloOptions {
+    pub trials: usize,
+    pub time_limit: Option<Duration>,
+    pub seed: Option<u64>
+}
+
+impl MonteCarloOptions {
+    pub fn new(trials: usize) -> MonteCarloOptions {
+        MonteCarloOptions {
+            trials: trials,
+            time_limit: None,
+            seed: None
+        }
+    }
+
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn make_rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        }
+    }
+}
+
+pub fn hold_em_monte_carlo(pockets: &[Vec<Card>], board: &[Card], hand_rank_counts: &mut [HandRankCount], options: &MonteCarloOptions) -> Vec<WinLoseSplit> {
+    game_monte_carlo(Game::HoldEm, pockets, board, &[], &WildCards::new(), hand_rank_counts, options)
+}
+
+/// Calls `visit` once per trial with the random number generator until the
+/// trial count or time limit in `options` runs out.
+pub fn for_each_trial<V>(options: &MonteCarloOptions, mut visit: V)
+where V: FnMut(&mut StdRng) {
+    let mut rng = options.make_rng();
+
+    let start = Instant::now();
+    for _trial in 0..options.trials {
+        if let Some(time_limit) = options.time_limit {
+            if start.elapsed() >= time_limit {
+                break;
+            }
+        }
+
+        visit(&mut rng);
+    }
+}
+
+/// Calls `visit` with `n` random cards drawn from `deck` until the trial
+/// count or time limit in `options` runs out.
+pub fn for_each_draw<V>(deck: &mut [Card], n: usize, options: &MonteCarloOptions, mut visit: V)
+where V: FnMut(&[Card]) {
+    for_each_trial(options, |rng| {
+        let (drawn, _rest) = deck.partial_shuffle(rng, n);
+        visit(drawn);
+    });
+}
+
+/// Calls `visit` with random completions of `board` drawn from `deck` until
+/// the trial count or time limit in `options` runs out.
+pub fn for_each_sample<V>(deck: &mut [Card], board: &[Card], options: &MonteCarloOptions, mut visit: V)
+where V: FnMut(&[&Card]) {
+    for_each_draw(deck, 5 - board.len(), options, |run_out| {
+        let complete_board = board.iter().chain(run_out.iter()).collect::<Vec<_>>();
+        visit(&complete_board);
+    });
+}
+
+/// Samples random completions of `board` for a community card game, with
+/// the cards in `wild` wild and the `dead` cards never dealt. Empty pockets
+/// are dealt a random hand each trial.
+pub fn game_monte_carlo(game: Game, pockets: &[Vec<Card>], board: &[Card], dead: &[Card], wild: &WildCards, hand_rank_counts: &mut [HandRankCount], options: &MonteCarloOptions) -> Vec<WinLoseSplit> {
+    let mut deck = game_deck(game, pockets, board, dead, wild);
+    let mut results = vec![WinLoseSplit::new(); pockets.len()];
+
+    if has_random_pockets(pockets) {
+        let (known, needed) = random_pocket_deal(game, pockets, board);
+        for_each_random_deal(&mut deck, &known, &needed, options, |dealt| {
+            let (complete_pockets, complete_board) = fill_random_pockets(pockets, dealt);
+            let board_refs = complete_board.iter().collect::<Vec<_>>();
+            let winners = find_game_winners(game, &complete_pockets, &board_refs, wild, hand_rank_counts);
+            record_winners(&mut results, &winners);
+        });
+        return results;
+    }
+
+    for_each_sample(&mut deck, board, options, |complete_board| {
+        let winners = find_game_winners(game, pockets, complete_board, wild, hand_rank_counts);
+        record_winners(&mut results, &winners);
+    });
+
+    return results;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use crate::hold_em::hold_em_odds;
+
+    fn simulate(pockets: &[&str], board: &str, options: &MonteCarloOptions) -> Vec<WinLoseSplit> {
+        let pockets = pockets.iter()
+            .map(|pocket| CardVector::parse(pocket).to_vec())
+            .collect::<Vec<_>>();
+        let board = CardVector::parse(board).to_vec();
+        let mut hand_rank_counts = pockets.iter()
+            .map(|_| HandRankCount::new())
+            .collect::<Vec<_>>();
+        hold_em_monte_carlo(&pockets, &board, &mut hand_rank_counts, options)
+    }
+
+    #[test]
+    fn test_trial_count() {
+        let results = simulate(&["AcAd", "KcKd"], "", &MonteCarloOptions::new(500).with_seed(1));
+        assert_eq!(results[0].total(), 500);
+        assert_eq!(results[0].wins, results[1].losses);
+        assert_eq!(results[0].splits, results[1].splits);
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let a = simulate(&["AcAd", "7h6h", "KsQs"], "", &MonteCarloOptions::new(300).with_seed(42));
+        let b = simulate(&["AcAd", "7h6h", "KsQs"], "", &MonteCarloOptions::new(300).with_seed(42));
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert_eq!(x.wins, y.wins);
+            assert_eq!(x.splits, y.splits);
+            assert_eq!(x.losses, y.losses);
+        }
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let options = MonteCarloOptions::new(usize::MAX).with_time_limit(Duration::from_millis(50));
+        let results = simulate(&["AcAd", "KcKd"], "", &options);
+        assert!(results[0].total() > 0);
+    }
+
+    #[test]
+    fn test_converges_to_enumeration() {
+        let pockets = vec![CardVector::parse("AcAd").to_vec(), CardVector::parse("KcKd").to_vec()];
+        let board = CardVector::parse("2s 7h 9d").to_vec();
+        let mut hand_rank_counts = vec![HandRankCount::new(), HandRankCount::new()];
+        let exact = hold_em_odds(&pockets, &board, &mut hand_rank_counts);
+
+        let sampled = simulate(&["AcAd", "KcKd"], "2s 7h 9d", &MonteCarloOptions::new(2000).with_seed(7));
+        let (low, high) = sampled[0].win_confidence_interval(4.0);
+        assert!(low <= exact[0].win_pct() && exact[0].win_pct() <= high);
+    }
+
+    #[test]
+    fn test_random_opponents() {
+        let results = simulate(&["AcAd", "", ""], "", &MonteCarloOptions::new(2000).with_seed(4));
+        assert_eq!(results[1].total(), 2000);
+        assert!(results[0].wins > results[1].wins);
+        assert!(results[0].wins > results[2].wins);
+    }
+}
@@ -1,19 +1,21 @@
 use strum::IntoEnumIterator;
 use std::cmp::Ordering;
+use itertools::Itertools;
 
-use super::card::{Suit, Rank, Card, IsWildCard, fmt_cards};
+use super::card::{Suit, Rank, Card, CardSet, IsWildCard, fmt_cards};
 
-fn filter_suit<'a>(cards: &'a [&'a Card], suit: Suit) -> impl Iterator<Item=&&'a Card> {
+fn filter_suit<'a>(cards: &'a [&'a Card], suit: Suit) -> impl Iterator<Item=&'a &'a Card> {
     cards.iter().filter(move |card| card.suit == suit)
 }
 
-fn filter_rank<'a>(cards: &'a [&'a Card], rank: Rank) -> impl Iterator<Item=&&'a Card> {
-    cards.iter().filter(move |card| card.rank == rank)
+fn filter_rank<'a>(cards: &'a [&'a Card], rank: Rank) -> impl Iterator<Item=&'a &'a Card> {
+    cards.iter().filter(move |card| card.scoring_rank == rank)
 }
 
 pub fn remove_cards<'a>(a: &'a [&Card], b: &[Card]) -> Vec<&'a Card> {
+    let dead = CardSet::from(b);
     let mut vec = a.to_vec();
-    vec.drain_filter(|card| b.contains(card));
+    vec.retain(|card| !dead.contains(card));
     return vec;
 }
 
@@ -62,6 +64,7 @@ macro_rules! define_set_maker {
     }
 }
 
+define_set_maker!(as_five_of_a_kind, &[5]);
 define_set_maker!(as_quads, &[4, 1]);
 define_set_maker!(as_full_house, &[3, 2]);
 define_set_maker!(as_trips, &[3, 1, 1]);
@@ -91,7 +94,7 @@ fn fill_straight(cards: &[&Card], wild_cards:&[&Card], rank_ordinal: usize, n: u
                 }
             }
 
-        if wild_cards.len() > 0 {
+        if !wild_cards.is_empty() {
             let rank = Rank::for_ordinal(rank_ordinal);
             if let Some(mut result) = fill_straight(cards, &wild_cards[1..], rank_ordinal - 1, n + 1) {
                 result.push(wild_cards[0].scored_as(rank));
@@ -115,19 +118,13 @@ fn as_straight(cards: &[&Card], wild_cards: &[&Card]) -> Option<Vec<Card>> {
 }
 
 fn find_missing_rank(cards: &[Card]) -> Option<Rank> {
-    for rank in Rank::iter() {
-        if !cards.iter().any(|card| card.scoring_rank == rank) {
-            return Some(rank);
-        }
-    }
-
-    return None;
+    Rank::iter().find(|rank| !cards.iter().any(|card| card.scoring_rank == *rank))
 }
 
 fn as_flush(cards: &[&Card], wild_cards: &[&Card]) -> Option<Vec<Card>> {
     for suit in Suit::iter() {
         if suit != Suit::Joker {
-            let suited_count = filter_suit(&cards, suit).count();
+            let suited_count = filter_suit(cards, suit).count();
             if suited_count + wild_cards.len() >= 5 {
                 let mut suited = filter_suit(cards, suit)
                     .cloned()
@@ -173,6 +170,8 @@ fn as_straight_flush(cards: &[&Card], wild_cards: &[&Card]) -> Option<Vec<Card>>
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Display, EnumIter)]
 pub enum HandRank {
+    #[strum(to_string = "Five of a Kind")]
+    FiveOfAKind = 9,
     #[strum(to_string = "Straight Flush")]
     StraightFlush = 8,
     #[strum(to_string = "Four of a Kind")]
@@ -196,6 +195,7 @@ pub enum HandRank {
 impl HandRank {
     pub fn build(&self) -> fn(&[&Card], &[&Card]) -> Option<Vec<Card>> {
         match self {
+            HandRank::FiveOfAKind => as_five_of_a_kind,
             HandRank::StraightFlush => as_straight_flush,
             HandRank::Quads => as_quads,
             HandRank::FullHouse => as_full_house,
@@ -208,6 +208,21 @@ impl HandRank {
         }
     }
 
+    /// The bug only plays as a wild card in straights and flushes.
+    fn bug_is_wild(&self) -> bool {
+        matches!(self, HandRank::StraightFlush | HandRank::Flush | HandRank::Straight)
+    }
+
+    pub fn for_ordinal(ordinal: usize) -> Self {
+        for rank in Self::iter() {
+            if rank as usize == ordinal {
+                return rank;
+            }
+        }
+
+        panic!("Invalid ordinal for HandRank!");
+    }
+
     fn score_cards(&self, cards: &[Card]) -> i32 {
         return cards.iter()
             .fold(*self as i32, |acc, card| acc * 16 + (card.scoring_rank as i32));
@@ -222,10 +237,16 @@ pub struct PokerHand {
 
 impl PokerHand {
     pub fn build(all_cards: &[&Card], is_wild: &Option<IsWildCard>) -> PokerHand {
+        PokerHand::build_with(all_cards, &|card: &Card| card.is_wild(is_wild))
+    }
+
+    /// Like `build`, with any predicate deciding which cards are wild, such
+    /// as a closure over the cards that are wild in one particular hand.
+    pub fn build_with(all_cards: &[&Card], is_wild: &dyn Fn(&Card) -> bool) -> PokerHand {
         let (wild_cards, cards): (Vec<&Card>, Vec<&Card>) = all_cards.iter()
             .cloned()
-            .partition(|card| card.is_wild(is_wild));
-        
+            .partition(|card| is_wild(card));
+
         for rank in HandRank::iter() {
             if let Some(cards) = rank.build()(&cards, &wild_cards) {
                 return PokerHand::new(rank, cards);
@@ -235,6 +256,63 @@ impl PokerHand {
         unreachable!();
     }
 
+    /// Like `build`, but the cards matching `is_bug` are only semi-wild: the
+    /// bug plays as an ace or completes a straight or flush.
+    pub fn build_bug(all_cards: &[&Card], is_bug: &Option<IsWildCard>) -> PokerHand {
+        PokerHand::build_bug_with(all_cards, &|card: &Card| card.is_wild(is_bug))
+    }
+
+    pub fn build_bug_with(all_cards: &[&Card], is_bug: &dyn Fn(&Card) -> bool) -> PokerHand {
+        let (bugs, cards): (Vec<&Card>, Vec<&Card>) = all_cards.iter()
+            .cloned()
+            .partition(|card| is_bug(card));
+
+        let aces = bugs.iter()
+            .map(|bug| bug.scored_as(Rank::Ace))
+            .collect::<Vec<_>>();
+        let cards_and_aces = cards.iter()
+            .cloned()
+            .chain(aces.iter())
+            .collect::<Vec<_>>();
+
+        for rank in HandRank::iter() {
+            let hand = if rank.bug_is_wild() {
+                rank.build()(&cards, &bugs)
+            } else {
+                rank.build()(&cards_and_aces, &[])
+            };
+
+            if let Some(cards) = hand {
+                return PokerHand::new(rank, cards);
+            }
+        }
+
+        unreachable!();
+    }
+
+    /// Best Omaha hand: exactly two cards from `hole` and three from `board`.
+    pub fn build_omaha(hole: &[&Card], board: &[&Card], is_wild: &Option<IsWildCard>) -> PokerHand {
+        PokerHand::build_omaha_with(hole, board, &|card: &Card| card.is_wild(is_wild))
+    }
+
+    pub fn build_omaha_with(hole: &[&Card], board: &[&Card], is_wild: &dyn Fn(&Card) -> bool) -> PokerHand {
+        let mut best: Option<PokerHand> = None;
+        for hole_cards in hole.iter().combinations(2) {
+            for board_cards in board.iter().combinations(3) {
+                let cards = hole_cards.iter()
+                    .chain(board_cards.iter())
+                    .map(|card| **card)
+                    .collect::<Vec<_>>();
+                let hand = PokerHand::build_with(&cards, is_wild);
+                if best.as_ref().is_none_or(|max| hand > *max) {
+                    best = Some(hand);
+                }
+            }
+        }
+
+        return best.expect("Omaha needs at least two hole cards and three board cards");
+    }
+
     fn new(hand_rank: HandRank, cards: Vec<Card>) -> Self {
         let score = hand_rank.score_cards(&cards);
         PokerHand {
@@ -279,6 +357,94 @@ impl std::fmt::Display for PokerHand {
     }
 }
 
+/// A Badugi hand: the largest set of up to four cards with distinct ranks
+/// and distinct suits, aces low. A bigger badugi always wins; between
+/// badugis of the same size the lower highest card wins, then the next.
+/// As with `LowHand`, a lower `score` is better and `Ord` is reversed.
+pub struct BadugiHand {
+    pub cards: Vec<Card>,
+    pub score: i32
+}
+
+fn is_badugi(cards: &[&Card]) -> bool {
+    let ranks = cards.iter().fold(0u32, |acc, card| acc | 1 << card.rank as u32);
+    let suits = cards.iter().fold(0u32, |acc, card| acc | 1 << card.suit as u32);
+    ranks.count_ones() as usize == cards.len() && suits.count_ones() as usize == cards.len()
+}
+
+impl BadugiHand {
+    pub fn build(all_cards: &[&Card]) -> BadugiHand {
+        for size in (1..=all_cards.len().min(4)).rev() {
+            let best = all_cards.iter()
+                .cloned()
+                .combinations(size)
+                .filter(|cards| is_badugi(cards))
+                .map(|cards| BadugiHand::new(&cards))
+                .max();
+
+            if let Some(hand) = best {
+                return hand;
+            }
+        }
+
+        return BadugiHand::new(&[]);
+    }
+
+    fn new(cards: &[&Card]) -> BadugiHand {
+        let cards = cards.iter()
+            .map(|card| card.scored_as(if card.rank == Rank::Ace { Rank::LowAce } else { card.rank }))
+            .sorted_by(|a, b| b.scoring_rank.cmp(&a.scoring_rank))
+            .collect::<Vec<_>>();
+
+        // Pad smaller badugis so every score has the same number of nibbles
+        let missing = 4 - cards.len() as i32;
+        let score = cards.iter()
+            .fold(missing, |acc, card| acc * 16 + card.scoring_rank as i32)
+            << (4 * missing);
+
+        BadugiHand {
+            cards: cards,
+            score: score
+        }
+    }
+
+    /// Number of cards in the badugi, four for a complete badugi.
+    pub fn size(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Ranks of the badugi from highest to lowest, aces as `Rank::LowAce`.
+    pub fn ranks(&self) -> Vec<Rank> {
+        self.cards.iter().map(|card| card.scoring_rank).collect()
+    }
+}
+
+impl PartialEq for BadugiHand {
+    fn eq(&self, other: &BadugiHand) -> bool {
+        self.score == other.score
+    }
+}
+
+impl Eq for BadugiHand {}
+
+impl Ord for BadugiHand {
+    fn cmp(&self, other: &BadugiHand) -> Ordering {
+        other.score.cmp(&self.score)
+    }
+}
+
+impl PartialOrd for BadugiHand {
+    fn partial_cmp(&self, other: &BadugiHand) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for BadugiHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} -> {} card Badugi", fmt_cards(&self.cards), self.size())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -299,16 +465,81 @@ mod tests {
         _parse_hand(card_string, &Some(Card::is_joker))
     }
 
+    fn parse_hand_bug(card_string: &str) -> PokerHand {
+        let card_vector = CardVector::parse(card_string);
+        PokerHand::build_bug(&card_vector.iter().collect::<Vec<_>>(), &Some(Card::is_joker))
+    }
+
     fn parse_hand_suicide_king(card_string: &str) -> PokerHand {
         _parse_hand(card_string, &Some(Card::is_suicide_king))
     }
 
+    fn parse_omaha(hole: &str, board: &str) -> PokerHand {
+        let hole = CardVector::parse(hole);
+        let board = CardVector::parse(board);
+        return PokerHand::build_omaha(&hole.iter().collect::<Vec<_>>(), &board.iter().collect::<Vec<_>>(), &None);
+    }
+
+    #[test]
+    fn test_omaha_must_use_two_hole_cards() {
+        assert_eq!(parse_omaha("Ts 9c 8c 7c", "As Ks Qs Js 2d").rank, Straight);
+        assert_eq!(parse_omaha("2c 3c 4d 5d", "Ac Ad Ah As Kd").rank, Triplets);
+        assert_eq!(parse_omaha("Ah Kd 7c 2s", "Ac 9h 8h 4h 3d").rank, OnePair);
+    }
+
+    #[test]
+    fn test_omaha_must_use_three_board_cards() {
+        assert_eq!(parse_omaha("Ac Kc Qc Jc", "Tc 7d 6d 2s 3h").rank, HighCard);
+        assert_eq!(parse_omaha("Ac Kc Qc Jc Tc 9c", "2c 3c 4c 5h 8s").rank, Flush);
+    }
+
+    #[test]
+    fn test_omaha_wild() {
+        let hole = CardVector::parse("?? Ad 7c 2s");
+        let board = CardVector::parse("Ac As 8h 4h 3d");
+        let hand = PokerHand::build_omaha(&hole.iter().collect::<Vec<_>>(), &board.iter().collect::<Vec<_>>(), &Some(Card::is_joker));
+        assert_eq!(hand.rank, Quads);
+    }
+
     #[test]
     fn test_println() {
         let poker_hand = parse_hand("Ac As Ad Ah Jd");
         println!("{}", poker_hand);
     }
 
+    #[test]
+    fn test_five_of_a_kind() {
+        let poker_hand = parse_hand("Ac As Ad Ah ??");
+        assert_eq!(poker_hand.rank, FiveOfAKind);
+        assert!(poker_hand.cards().iter().all(|card| card.scoring_rank == Ace));
+
+        assert_eq!(parse_hand("7c 7s ?? ?? 7d").rank, FiveOfAKind);
+        assert_eq!(parse_hand("?? ?? ?? ?? ??").rank, FiveOfAKind);
+        assert_eq!(parse_hand_suicide_king("Kh 9c 9s 9d 9h").rank, FiveOfAKind);
+        assert_eq!(_parse_hand("2c 2s 2d Jh Js", &Some(Card::is_one_eyed_jack)).rank, FiveOfAKind);
+    }
+
+    #[test]
+    fn test_five_of_a_kind_beats_straight_flush() {
+        let five_of_a_kind = parse_hand("2c 2s 2d 2h ??");
+        let straight_flush = parse_hand("Ac Kc Qc Jc Tc");
+        assert_eq!(five_of_a_kind.cmp(&straight_flush), Ordering::Greater);
+
+        let aces = parse_hand("Ac As Ad ?? ??");
+        assert_eq!(aces.cmp(&five_of_a_kind), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_five_of_a_kind_from_deck_with_jokers() {
+        let deck = crate::deck::make_deck_with_jokers(2);
+        let cards = deck.iter()
+            .filter(|card| card.rank == Ace || card.rank == Rank::Joker)
+            .take(5)
+            .collect::<Vec<_>>();
+        let poker_hand = PokerHand::build(&cards, &Some(Card::is_joker));
+        assert_eq!(poker_hand.rank, FiveOfAKind);
+    }
+
     #[test]
     fn test_straight_flush() {
         assert_eq!(parse_hand("Ac Kc Qc Tc Jc").rank, StraightFlush);
@@ -436,6 +667,72 @@ mod tests {
         assert_eq!(parse_hand_suicide_king("Ac Kh 5c 6c 7d").rank, OnePair);
     }
 
+    #[test]
+    fn test_bug_against_jokers() {
+        // The joker hands from the tests above, with the rank each makes
+        // with a joker and with a bug. The bug plays the same as the joker
+        // when the joker is an ace or completes a straight or flush, and is
+        // only ever worse
+        let cases = [("Ac As Ad Ah ??", FiveOfAKind, FiveOfAKind),
+                     ("7c 7s ?? ?? 7d", FiveOfAKind, FullHouse),
+                     ("?? ?? ?? ?? ??", FiveOfAKind, FiveOfAKind),
+                     ("2c 2s 2d 2h ??", FiveOfAKind, Quads),
+                     ("Ac As Ad ?? ??", FiveOfAKind, FiveOfAKind),
+                     ("Ac Kc Qc ?? Jc", StraightFlush, StraightFlush),
+                     ("Ac Kc ?? ?? Jc", StraightFlush, StraightFlush),
+                     ("Ac As Ad ?? Jd", Quads, Quads),
+                     ("Ac As ?? Jc Jd", FullHouse, FullHouse),
+                     ("Ac As ?? Jc Td", Triplets, Triplets),
+                     ("Ac ?? Jc Td 7c", OnePair, OnePair),
+                     ("Ac As ?? ?? Jd", Quads, Quads),
+                     ("Ac ?? ?? Td 7c", Triplets, Triplets),
+                     ("Ac Kc 7c Tc ??", Flush, Flush),
+                     ("Ac Kc ?? Jc 7c", Flush, Flush),
+                     ("5c 4s 3s 2d ??", Straight, Straight),
+                     ("?? 4s 3s 2d Ac", Straight, Straight),
+                     ("Ac Kc Qc Jd ??", Straight, Straight),
+                     ("Ac Kc ?? ?? Ts", Straight, Straight),
+                     ("Ac As ?? Jh Jd", FullHouse, FullHouse),
+                     ("Ac Ks ?? Jh Td", Straight, Straight),
+                     ("Ac Ks ?? ?? Td", Straight, Straight),
+                     ("Ac ?? Qc 7c 6c", Flush, Flush),
+                     ("Ac ?? ?? 7c 6c", Flush, Flush)];
+
+        for (cards, joker_rank, bug_rank) in cases.iter() {
+            let joker = parse_hand(cards);
+            let bug = parse_hand_bug(cards);
+            assert_eq!(joker.rank, *joker_rank, "{}", cards);
+            assert_eq!(bug.rank, *bug_rank, "{}", cards);
+
+            let expected = if joker_rank == bug_rank { Ordering::Equal } else { Ordering::Less };
+            assert_eq!(bug.cmp(&joker), expected, "{}", cards);
+        }
+    }
+
+    #[test]
+    fn test_bug() {
+        // Away from the joker cases, the bug is never a king or a jack
+        assert_eq!(parse_hand_bug("Kc Ks ?? Jc Jd").rank, TwoPair);
+        assert_eq!(parse_hand_bug("Kc Ks ?? Jc Td").rank, OnePair);
+        assert_eq!(parse_hand("Kc Ks ?? Jc Td").rank, Triplets);
+
+        assert_eq!(parse_hand_bug("Kc ?? Qd Jh 7d").cmp(&parse_hand("Kc Ad Qd Jh 7d")), Ordering::Equal);
+        assert_eq!(parse_hand_bug("Ac Kc ?? Jh 7d").cmp(&parse_hand("Ac Kc Ad Jh 7d")), Ordering::Equal);
+        assert_eq!(parse_hand_bug("Ac Kc ?? Jc 7c").cmp(&parse_hand("Ac Kc Qc Jc 7c")), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_filter_rank_uses_scoring_rank() {
+        // A bug scored as an ace groups with the natural aces. Other cards
+        // score as their own rank, so filtering them is unchanged
+        let cards = CardVector::parse("Ac Kd ??");
+        let bug = cards[2].scored_as(Ace);
+        assert_eq!(filter_rank(&[&cards[0], &cards[1], &bug], Ace).count(), 2);
+        assert_eq!(filter_rank(&[&cards[0], &cards[1], &bug], Rank::Joker).count(), 0);
+        assert_eq!(filter_rank(&cards.iter().collect::<Vec<_>>(), Rank::Joker).count(), 1);
+        assert_eq!(filter_rank(&cards.iter().collect::<Vec<_>>(), Ace).count(), 1);
+    }
+
     #[test]
     fn test_two_pair_edge_case() {
         let poker_hand = parse_hand("K♣ K♦ 5♠ 5♣ 3♥ 3♣");
@@ -496,4 +793,36 @@ mod tests {
         assert_eq!(wild_two.cmp(&natural), Ordering::Equal);
         assert_eq!(wild_two.cmp(&wild_one), Ordering::Equal);
     }
+
+    fn badugi(card_string: &str) -> BadugiHand {
+        let cards = CardVector::parse(card_string);
+        return BadugiHand::build(&cards.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_badugi_size() {
+        assert_eq!(badugi("Ac 2d 3h 4s").size(), 4);
+        assert_eq!(badugi("Ac 2c 3h 4s").size(), 3);
+        assert_eq!(badugi("Ac Ad 3h 4s").size(), 3);
+        assert_eq!(badugi("Ac 2c 3h 4h").size(), 2);
+        assert_eq!(badugi("Ac 2c 3c 4c").size(), 1);
+        assert_eq!(badugi("Ac 2c 3c 4c").ranks(), vec![LowAce]);
+    }
+
+    #[test]
+    fn test_badugi_ranks() {
+        // The 2c makes a better three card badugi than the Kc
+        assert_eq!(badugi("Kc 2c 3h 4s").ranks(), vec![Four, Three, Two]);
+        assert_eq!(badugi("Kc 2c 3h 4s 5d").ranks(), vec![Five, Four, Three, Two]);
+        assert_eq!(badugi("Kd 7c 3h As").ranks(), vec![King, Seven, Three, LowAce]);
+    }
+
+    #[test]
+    fn test_cmp_badugi() {
+        assert!(badugi("Ac 2d 3h 4s") > badugi("Ac 2d 3h 5s"));
+        assert!(badugi("Kd Qc Jh Ts") > badugi("Ac 2d 3h 4h"));
+        assert!(badugi("7d 4c 3h As") > badugi("7d 5c 2h As"));
+        assert!(badugi("Ac 2d 3h 4s") == badugi("As 2h 3d 4c"));
+        assert!(badugi("Ac 2d 3h 3s") > badugi("Ac 2d 4h 4s"));
+    }
 }
@@ -0,0 +1,146 @@
+use super::win_lose_split::WinLoseSplit;
+
+/// Results for one player across many split pots. `high` and `low` count the
+/// halves separately (`low` only on boards where some hand made a qualifying
+/// low), while `equity` sums the fraction of each pot the player won.
+#[derive(Copy, Clone, Default)]
+pub struct PotEquity {
+    pub high: WinLoseSplit,
+    pub low: WinLoseSplit,
+    pub scoops: i32,
+    pub pots: i32,
+    pub equity: f64
+}
+
+impl PotEquity {
+    pub fn new() -> PotEquity {
+        PotEquity {
+            high: WinLoseSplit::new(),
+            low: WinLoseSplit::new(),
+            scoops: 0,
+            pots: 0,
+            equity: 0.0
+        }
+    }
+
+    pub fn equity_pct(&self) -> f64 {
+        100.0 * self.equity / (self.pots as f64)
+    }
+
+    pub fn scoop_pct(&self) -> f64 {
+        100.0 * (self.scoops as f64) / (self.pots as f64)
+    }
+
+    /// Percentage of pots in which some player made a qualifying low.
+    pub fn low_pct(&self) -> f64 {
+        100.0 * (self.low.total() as f64) / (self.pots as f64)
+    }
+}
+
+/// Splits one pot between the high and low winners. Without a qualifying low
+/// the high winners share the whole pot; otherwise each half is divided among
+/// its winners, so a tied half is quartered.
+pub fn award_pot(results: &mut [PotEquity], high_winners: &[usize], low_winners: &[usize]) {
+    award_weighted_pot(results, high_winners, low_winners, 1)
+}
+
+/// Like `award_pot`, for `weight` pots with the same winners.
+pub fn award_weighted_pot(results: &mut [PotEquity], high_winners: &[usize], low_winners: &[usize], weight: i32) {
+    let high_share = if low_winners.is_empty() { 1.0 } else { 0.5 } / (high_winners.len() as f64);
+    let low_share = 0.5 / (low_winners.len().max(1) as f64);
+
+    for (index, result) in results.iter_mut().enumerate() {
+        let mut share = 0.0;
+
+        if high_winners.contains(&index) {
+            share += high_share;
+            if high_winners.len() == 1 {
+                result.high.wins += weight;
+            } else {
+                result.high.splits += weight;
+            }
+        } else {
+            result.high.losses += weight;
+        }
+
+        if !low_winners.is_empty() {
+            if low_winners.contains(&index) {
+                share += low_share;
+                if low_winners.len() == 1 {
+                    result.low.wins += weight;
+                } else {
+                    result.low.splits += weight;
+                }
+            } else {
+                result.low.losses += weight;
+            }
+        }
+
+        if share >= 1.0 {
+            result.scoops += weight;
+        }
+        result.pots += weight;
+        result.equity += share * weight as f64;
+    }
+}
+
+impl std::ops::AddAssign for PotEquity {
+    fn add_assign(&mut self, other: PotEquity) {
+        self.high += other.high;
+        self.low += other.low;
+        self.scoops += other.scoops;
+        self.pots += other.pots;
+        self.equity += other.equity;
+    }
+}
+
+impl std::fmt::Display for PotEquity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.2}% equity - {:.2}% scoop - high {} - low {}",
+               self.equity_pct(), self.scoop_pct(), self.high, self.low)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoop_without_low() {
+        let mut results = vec![PotEquity::new(); 2];
+        award_pot(&mut results, &[0], &[]);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(results[0].scoops, 1);
+        assert_eq!(results[1].equity, 0.0);
+        assert_eq!(results[0].low.total(), 0);
+    }
+
+    #[test]
+    fn test_scoop_with_low() {
+        let mut results = vec![PotEquity::new(); 2];
+        award_pot(&mut results, &[1], &[1]);
+        assert_eq!(results[1].equity, 1.0);
+        assert_eq!(results[1].scoops, 1);
+        assert_eq!(results[0].low.losses, 1);
+    }
+
+    #[test]
+    fn test_split_halves() {
+        let mut results = vec![PotEquity::new(); 2];
+        award_pot(&mut results, &[0], &[1]);
+        assert_eq!(results[0].equity, 0.5);
+        assert_eq!(results[1].equity, 0.5);
+        assert_eq!(results[0].scoops + results[1].scoops, 0);
+    }
+
+    #[test]
+    fn test_quartered() {
+        let mut results = vec![PotEquity::new(); 3];
+        award_pot(&mut results, &[0], &[0, 1]);
+        assert_eq!(results[0].equity, 0.75);
+        assert_eq!(results[1].equity, 0.25);
+        assert_eq!(results[2].equity, 0.0);
+        assert_eq!(results[0].low.splits, 1);
+        assert_eq!(results[2].high.losses, 1);
+    }
+}
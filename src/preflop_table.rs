@@ -0,0 +1,386 @@
+//! Heads-up preflop hold'em equity for every pair of starting hands,
+//! enumerated once by `PreflopTable::generate` and saved to a compact binary
+//! file, so that later queries are a lookup instead of 1.7M boards.
+//!
+//! Matchups that differ only by a suit permutation or by which player is the
+//! hero have the same result, so the table holds one entry for each class of
+//! them, 47,008 in all. The file records a checksum of the hand evaluator and
+//! loading refuses a table built with a different one.
+
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use itertools::Itertools;
+
+use super::card::{Card, CardSet};
+use super::deck::make_deck;
+use super::game::Game;
+use super::hand_rank_count::HandRankCount;
+use super::hold_em::game_odds;
+use super::suit_symmetry::{SuitPermutation, symmetries};
+use super::wild::WildCards;
+use super::win_lose_split::WinLoseSplit;
+
+const MAGIC: &[u8; 4] = b"PFEQ";
+const VERSION: u32 = 1;
+
+/// Bytes in each entry: the key, wins and splits.
+const RECORD_SIZE: usize = 12;
+
+/// Seven card hands scored for the evaluator checksum.
+const CHECKSUM_HANDS: usize = 100_000;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x100_0000_01b3;
+
+/// Number of classes of heads-up matchups, and so the most entries a table has.
+pub const MATCHUP_CLASSES: usize = 47_008;
+
+/// Number of boards dealt from the 48 cards left after two pockets.
+pub const PREFLOP_BOARDS: i32 = 1_712_304;
+
+/// A matchup as the card indices of the hero's pocket and then the
+/// villain's, each high card first, six bits apiece.
+type MatchupKey = u32;
+
+fn pocket_bits(cards: CardSet) -> u32 {
+    let bits = cards.bits();
+    return (63 - bits.leading_zeros()) << 6 | bits.trailing_zeros();
+}
+
+fn matchup_key(hero: CardSet, villain: CardSet) -> MatchupKey {
+    pocket_bits(hero) << 12 | pocket_bits(villain)
+}
+
+fn key_pockets(key: MatchupKey) -> (Vec<Card>, Vec<Card>) {
+    let card = |shift: u32| Card::from_index((key >> shift & 0x3f) as usize);
+    (vec![card(18), card(12)], vec![card(6), card(0)])
+}
+
+/// The lowest key of any matchup in the class of `hero` against `villain`,
+/// and whether the players trade places in it.
+fn canonical_key(hero: CardSet, villain: CardSet, permutations: &[SuitPermutation]) -> (MatchupKey, bool) {
+    let mut best = (MatchupKey::MAX, false);
+    for permutation in permutations {
+        let (hero, villain) = (permutation.apply(hero), permutation.apply(villain));
+        best = best.min((matchup_key(hero, villain), false)).min((matchup_key(villain, hero), true));
+    }
+    return best;
+}
+
+fn is_pocket(cards: &[Card]) -> bool {
+    cards.len() == 2 && !cards.iter().any(Card::is_joker) && CardSet::from(cards).len() == 2
+}
+
+/// The same result from the other player's side.
+fn swap(result: WinLoseSplit) -> WinLoseSplit {
+    WinLoseSplit {
+        wins: result.losses,
+        losses: result.wins,
+        splits: result.splits
+    }
+}
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Fingerprint of the hold'em evaluator: a hash of its scores for a fixed
+/// pseudo-random sample of seven card hands. Any change to how hands score
+/// changes the checksum and so invalidates saved tables. Computed once per
+/// process.
+pub fn evaluator_checksum() -> u64 {
+    static CHECKSUM: OnceLock<u64> = OnceLock::new();
+    *CHECKSUM.get_or_init(compute_evaluator_checksum)
+}
+
+fn compute_evaluator_checksum() -> u64 {
+    let mut hash = fnv1a(FNV_OFFSET, &VERSION.to_le_bytes());
+    let mut state = 0u64;
+    for _hand in 0..CHECKSUM_HANDS {
+        let mut cards = CardSet::new();
+        while cards.len() < 7 {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            cards.insert(&Card::from_index((state >> 33) as usize % 52));
+        }
+
+        let cards = cards.to_vec();
+        let board = cards[2..].iter().collect::<Vec<_>>();
+        let (_rank, score) = Game::HoldEm.score(&cards[..2], &board, CardSet::from(&cards[2..]), &WildCards::new());
+        hash = fnv1a(hash, &score.to_le_bytes());
+    }
+    return hash;
+}
+
+/// Exact heads-up preflop results, looked up by the two pockets.
+pub struct PreflopTable {
+    checksum: u64,
+    entries: Vec<(MatchupKey, WinLoseSplit)>
+}
+
+impl PreflopTable {
+    /// One matchup from each class, as the hero's pocket and the villain's.
+    pub fn matchups() -> Vec<(Vec<Card>, Vec<Card>)> {
+        let permutations = symmetries(&[]);
+        let pockets = make_deck().iter()
+            .combinations(2)
+            .map(|pocket| pocket.into_iter().collect::<CardSet>())
+            .collect::<Vec<_>>();
+
+        let mut keys = BTreeSet::new();
+        for hero in pockets.iter() {
+            for villain in pockets.iter().filter(|villain| villain.is_disjoint(hero)) {
+                keys.insert(canonical_key(*hero, *villain, &permutations).0);
+            }
+        }
+        return keys.into_iter().map(key_pockets).collect();
+    }
+
+    /// Enumerates every matchup, which takes some hours. `progress` is
+    /// called with the number of matchups done and the total after each.
+    pub fn generate<P>(threads: usize, progress: P) -> PreflopTable
+    where P: Fn(usize, usize) + Sync {
+        PreflopTable::generate_matchups(&PreflopTable::matchups(), threads, progress)
+    }
+
+    /// Enumerates just the classes of `matchups`, striped across `threads`.
+    pub fn generate_matchups<P>(matchups: &[(Vec<Card>, Vec<Card>)], threads: usize, progress: P) -> PreflopTable
+    where P: Fn(usize, usize) + Sync {
+        let permutations = symmetries(&[]);
+        let threads = threads.max(1);
+        let done = AtomicUsize::new(0);
+
+        let mut entries = std::thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|stripe| {
+                    let (permutations, done, progress) = (&permutations, &done, &progress);
+                    scope.spawn(move || {
+                        matchups.iter()
+                            .skip(stripe)
+                            .step_by(threads)
+                            .map(|(hero, villain)| {
+                                let (key, swapped) = canonical_key(CardSet::from(hero.as_slice()), CardSet::from(villain.as_slice()), permutations);
+                                let pockets = vec![hero.clone(), villain.clone()];
+                                let results = game_odds(Game::HoldEm, &pockets, &[], &[], &WildCards::new(), &mut HandRankCount::for_players(2), 1);
+                                progress(done.fetch_add(1, Ordering::Relaxed) + 1, matchups.len());
+                                (key, results[swapped as usize])
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles.into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        entries.sort_by_key(|(key, _result)| *key);
+        entries.dedup_by_key(|(key, _result)| *key);
+        PreflopTable {
+            checksum: evaluator_checksum(),
+            entries: entries
+        }
+    }
+
+    /// Number of matchup classes in the table.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Results for `hero` against `villain` with no board or dead cards, or
+    /// `None` when the table does not cover them.
+    pub fn lookup(&self, hero: &[Card], villain: &[Card]) -> Option<WinLoseSplit> {
+        if !is_pocket(hero) || !is_pocket(villain) {
+            return None;
+        }
+
+        let (hero, villain) = (CardSet::from(hero), CardSet::from(villain));
+        if !hero.is_disjoint(&villain) {
+            return None;
+        }
+
+        let (key, swapped) = canonical_key(hero, villain, &symmetries(&[]));
+        let index = self.entries.binary_search_by_key(&key, |(key, _result)| *key).ok()?;
+        let result = self.entries[index].1;
+        return Some(if swapped { swap(result) } else { result });
+    }
+
+    /// Writes the table: a header with the evaluator checksum and the number
+    /// of entries, the entries, then a checksum of the entries.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut records = Vec::with_capacity(RECORD_SIZE * self.entries.len());
+        for (key, result) in self.entries.iter() {
+            records.extend_from_slice(&key.to_le_bytes());
+            records.extend_from_slice(&(result.wins as u32).to_le_bytes());
+            records.extend_from_slice(&(result.splits as u32).to_le_bytes());
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&self.checksum.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        writer.write_all(&records)?;
+        writer.write_all(&fnv1a(FNV_OFFSET, &records).to_le_bytes())
+    }
+
+    /// Reads a table written by `write`, failing if it is damaged or was
+    /// generated with a different evaluator.
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<PreflopTable> {
+        let mut header = [0u8; 20];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC || u32::from_le_bytes(header[4..8].try_into().unwrap()) != VERSION {
+            return Err(invalid_data("Not a preflop equity table"));
+        }
+
+        let checksum = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        if checksum != evaluator_checksum() {
+            return Err(invalid_data("Preflop equity table was generated with a different evaluator"));
+        }
+
+        let count = u32::from_le_bytes(header[16..20].try_into().unwrap()) as usize;
+        if count > MATCHUP_CLASSES {
+            return Err(invalid_data("Preflop equity table is corrupt"));
+        }
+        let mut records = vec![0u8; RECORD_SIZE * count];
+        let mut trailer = [0u8; 8];
+        reader.read_exact(&mut records)?;
+        reader.read_exact(&mut trailer)?;
+        if u64::from_le_bytes(trailer) != fnv1a(FNV_OFFSET, &records) {
+            return Err(invalid_data("Preflop equity table is corrupt"));
+        }
+
+        // `lookup` binary searches the keys, and the losses are whatever
+        // boards are left over, so check both before trusting the entries.
+        let field = |record: &[u8], i: usize| u32::from_le_bytes(record[4 * i..4 * i + 4].try_into().unwrap());
+        let mut entries: Vec<(u32, WinLoseSplit)> = Vec::with_capacity(count);
+        for record in records.chunks_exact(RECORD_SIZE) {
+            let (key, wins, splits) = (field(record, 0), field(record, 1), field(record, 2));
+            if entries.last().is_some_and(|(last, _result)| *last >= key) {
+                return Err(invalid_data("Preflop equity table keys are out of order"));
+            }
+            if wins as u64 + splits as u64 > PREFLOP_BOARDS as u64 {
+                return Err(invalid_data("Preflop equity table has more results than boards"));
+            }
+
+            let (wins, splits) = (wins as i32, splits as i32);
+            entries.push((key, WinLoseSplit { wins: wins, losses: PREFLOP_BOARDS - wins - splits, splits: splits }));
+        }
+
+        return Ok(PreflopTable {
+            checksum: checksum,
+            entries: entries
+        });
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<PreflopTable> {
+        PreflopTable::read(&mut io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use crate::hold_em::hold_em_odds;
+
+    fn pockets(hero: &str, villain: &str) -> (Vec<Card>, Vec<Card>) {
+        (CardVector::parse(hero).to_vec(), CardVector::parse(villain).to_vec())
+    }
+
+    fn result(result: WinLoseSplit) -> (i32, i32, i32) {
+        (result.wins, result.losses, result.splits)
+    }
+
+    #[test]
+    fn test_matchup_classes() {
+        assert_eq!(PreflopTable::matchups().len(), MATCHUP_CLASSES);
+
+        let permutations = symmetries(&[]);
+        let key = |hero: &str, villain: &str| canonical_key(CardSet::from(&CardVector::parse(hero)), CardSet::from(&CardVector::parse(villain)), &permutations);
+        assert_eq!(key("As Ks", "Qs Js").0, key("Ah Kh", "Qh Jh").0);
+        assert_eq!(key("As Ks", "Qs Js").0, key("Qd Jd", "Ad Kd").0);
+        assert_ne!(key("As Ks", "Qs Js").1, key("Qd Jd", "Ad Kd").1);
+        assert_ne!(key("As Ks", "Qs Js").0, key("As Ks", "Qh Jh").0);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let matchups = vec![pockets("As Ks", "Qs Js"), pockets("Ac Ad", "Kc Kd")];
+        let table = PreflopTable::generate_matchups(&matchups, 2, |_done, _total| {});
+        assert_eq!(table.len(), 2);
+
+        let (hero, villain) = &matchups[0];
+        let expected = hold_em_odds(&[hero.clone(), villain.clone()], &[], &mut HandRankCount::for_players(2));
+        assert_eq!(result(table.lookup(hero, villain).unwrap()), result(expected[0]));
+        assert_eq!(expected[0].total(), PREFLOP_BOARDS);
+
+        let (hero, villain) = pockets("Qh Jh", "Ah Kh");
+        assert_eq!(result(table.lookup(&hero, &villain).unwrap()), result(expected[1]));
+        let (hero, villain) = pockets("Kh Ks", "Ah As");
+        assert_eq!(table.lookup(&hero, &villain).unwrap().wins, table.lookup(&matchups[1].0, &matchups[1].1).unwrap().losses);
+
+        let (hero, villain) = pockets("As Ks", "Qh Jh");
+        assert!(table.lookup(&hero, &villain).is_none());
+        let (hero, villain) = pockets("As Ks", "As Qh");
+        assert!(table.lookup(&hero, &villain).is_none());
+    }
+
+    #[test]
+    fn test_read_and_write() {
+        let matchups = vec![pockets("As Ks", "Qs Js")];
+        let table = PreflopTable::generate_matchups(&matchups, 1, |_done, _total| {});
+
+        let mut bytes = Vec::new();
+        table.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 20 + RECORD_SIZE + 8);
+
+        let read = PreflopTable::read(&mut bytes.as_slice()).unwrap();
+        let (hero, villain) = &matchups[0];
+        assert_eq!(result(read.lookup(hero, villain).unwrap()), result(table.lookup(hero, villain).unwrap()));
+
+        let mut corrupt = bytes.clone();
+        corrupt[24] ^= 1;
+        assert!(PreflopTable::read(&mut corrupt.as_slice()).is_err());
+
+        let mut too_long = bytes.clone();
+        too_long[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(PreflopTable::read(&mut too_long.as_slice()).err().unwrap().to_string(), "Preflop equity table is corrupt");
+
+        let matchups = vec![pockets("As Ks", "Qs Js"), pockets("Ac Ad", "Kc Kd")];
+        let mut unsorted = PreflopTable::generate_matchups(&matchups, 1, |_done, _total| {});
+        unsorted.entries.reverse();
+        let mut unsorted_bytes = Vec::new();
+        unsorted.write(&mut unsorted_bytes).unwrap();
+        assert_eq!(PreflopTable::read(&mut unsorted_bytes.as_slice()).err().unwrap().to_string(), "Preflop equity table keys are out of order");
+
+        let mut too_many = PreflopTable::generate_matchups(&matchups[..1], 1, |_done, _total| {});
+        too_many.entries[0].1.splits = PREFLOP_BOARDS;
+        let mut too_many_bytes = Vec::new();
+        too_many.write(&mut too_many_bytes).unwrap();
+        assert_eq!(PreflopTable::read(&mut too_many_bytes.as_slice()).err().unwrap().to_string(), "Preflop equity table has more results than boards");
+
+        let mut other_evaluator = bytes.clone();
+        other_evaluator[8] ^= 1;
+        let err = PreflopTable::read(&mut other_evaluator.as_slice()).err().unwrap();
+        assert_eq!(err.to_string(), "Preflop equity table was generated with a different evaluator");
+    }
+}
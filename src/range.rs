@@ -0,0 +1,273 @@
+//! Hold'em starting hand ranges in the usual shorthand, e.g.
+//! `QQ+, AKs, A5s-A2s, KTo+, AhKh, AQs:0.5`.
+//!
+//! A range is a list of parts separated by commas or spaces. Each part is a
+//! pair (`QQ`), a suited or offsuit hand (`AKs`, `AKo`, or `AK` for both), a
+//! specific combo (`AhKh`), a `+` range raising the pair or the kicker up to
+//! the top (`QQ+`, `KTo+`) or a `-` range between two hands with the same
+//! shape (`QQ-88`, `A5s-A2s`). A part may end in `:weight`, the fraction of
+//! the time the range holds its combos.
+
+use strum::IntoEnumIterator;
+
+use super::card::{Suit, Rank, Card, CardSet, fmt_cards};
+
+type ParseError = &'static str;
+
+/// A concrete pocket and how often the range holds it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Combo {
+    pub cards: Vec<Card>,
+    pub weight: f64
+}
+
+impl Combo {
+    pub fn new(mut cards: Vec<Card>, weight: f64) -> Combo {
+        cards.sort_by(|a, b| b.cmp(a));
+        Combo {
+            cards: cards,
+            weight: weight
+        }
+    }
+
+    pub fn card_set(&self) -> CardSet {
+        CardSet::from(self.cards.as_slice())
+    }
+}
+
+impl std::fmt::Display for Combo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.weight < 1.0 {
+            write!(f, "{} ({:.0}%)", fmt_cards(&self.cards), 100.0 * self.weight)
+        } else {
+            write!(f, "{}", fmt_cards(&self.cards))
+        }
+    }
+}
+
+/// The distinct combos in a range. A combo listed twice keeps its last weight.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HandRange {
+    pub combos: Vec<Combo>
+}
+
+impl HandRange {
+    pub fn new() -> HandRange {
+        HandRange {
+            combos: Vec::new()
+        }
+    }
+
+    /// Adds `cards` to the range, replacing the weight of an existing combo.
+    pub fn add(&mut self, cards: Vec<Card>, weight: f64) {
+        let combo = Combo::new(cards, weight);
+        match self.combos.iter_mut().find(|existing| existing.card_set() == combo.card_set()) {
+            Some(existing) => existing.weight = weight,
+            None => self.combos.push(combo)
+        }
+    }
+
+    /// Number of distinct combos.
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+
+    /// Number of combos counting each by its weight.
+    pub fn weighted_len(&self) -> f64 {
+        self.combos.iter().map(|combo| combo.weight).sum()
+    }
+
+    /// The combos that do not use any of the `dead` cards.
+    pub fn without(&self, dead: &CardSet) -> HandRange {
+        HandRange {
+            combos: self.combos.iter()
+                .filter(|combo| combo.card_set().is_disjoint(dead))
+                .cloned()
+                .collect()
+        }
+    }
+}
+
+fn parse_rank(ch: char) -> Result<Rank, ParseError> {
+    match ch.to_string().parse::<Rank>()? {
+        Rank::Joker => Err("Invalid rank in range"),
+        rank => Ok(rank)
+    }
+}
+
+/// A starting hand class: high rank, low rank and whether it is suited,
+/// with `None` meaning both suited and offsuit.
+type HandClass = (Rank, Rank, Option<bool>);
+
+fn parse_class(str: &str) -> Result<HandClass, ParseError> {
+    let chars = str.chars().collect::<Vec<_>>();
+    if chars.len() < 2 || chars.len() > 3 {
+        return Err("Invalid hand in range");
+    }
+
+    let (a, b) = (parse_rank(chars[0])?, parse_rank(chars[1])?);
+    let (high, low) = if a >= b { (a, b) } else { (b, a) };
+    let suited = match chars.get(2) {
+        None => None,
+        Some(_) if high == low => return Err("Pairs are neither suited nor offsuit"),
+        Some('s') => Some(true),
+        Some('o') => Some(false),
+        Some(_) => return Err("Invalid hand in range")
+    };
+
+    return Ok((high, low, suited));
+}
+
+fn class_combos((high, low, suited): HandClass) -> Vec<Vec<Card>> {
+    let mut combos = Vec::new();
+    let suits = Suit::iter().take(4).collect::<Vec<_>>();
+    for (i, high_suit) in suits.iter().enumerate() {
+        for (j, low_suit) in suits.iter().enumerate() {
+            let wanted = if high == low {
+                i < j
+            } else {
+                suited.is_none_or(|suited| suited == (i == j))
+            };
+            if wanted {
+                combos.push(vec![high.of(*high_suit), low.of(*low_suit)]);
+            }
+        }
+    }
+    return combos;
+}
+
+fn ranks_between(low: Rank, high: Rank) -> impl Iterator<Item=Rank> {
+    (low as usize..=high as usize).rev().map(Rank::for_ordinal)
+}
+
+/// The classes in a `+` or `-` range, or the single class otherwise.
+fn parse_classes(part: &str) -> Result<Vec<HandClass>, ParseError> {
+    if let Some(base) = part.strip_suffix('+') {
+        let (high, low, suited) = parse_class(base)?;
+        if high == low {
+            return Ok(ranks_between(low, Rank::Ace).map(|rank| (rank, rank, None)).collect());
+        }
+        let top_kicker = Rank::for_ordinal(high as usize - 1);
+        return Ok(ranks_between(low, top_kicker).map(|kicker| (high, kicker, suited)).collect());
+    }
+
+    if let Some((from, to)) = part.split_once('-') {
+        let (from, to) = (parse_class(from)?, parse_class(to)?);
+        if from.0 == from.1 && to.0 == to.1 {
+            let (low, high) = if from.0 <= to.0 { (from.0, to.0) } else { (to.0, from.0) };
+            return Ok(ranks_between(low, high).map(|rank| (rank, rank, None)).collect());
+        }
+        if from.0 == to.0 && from.2 == to.2 && from.0 != from.1 && to.0 != to.1 {
+            let (low, high) = if from.1 <= to.1 { (from.1, to.1) } else { (to.1, from.1) };
+            return Ok(ranks_between(low, high).map(|kicker| (from.0, kicker, from.2)).collect());
+        }
+        return Err("Both ends of a range must be pairs or share their high card");
+    }
+
+    return Ok(vec![parse_class(part)?]);
+}
+
+fn parse_combo(part: &str) -> Result<Vec<Card>, ParseError> {
+    let cards = vec![part[0..2].parse::<Card>()?, part[2..4].parse::<Card>()?];
+    if cards[0] == cards[1] || cards.iter().any(|card| card.rank == Rank::Joker) {
+        return Err("Invalid combo in range");
+    }
+    return Ok(cards);
+}
+
+fn is_combo(part: &str) -> bool {
+    part.len() == 4 && part.is_ascii() && "cdhs".contains(&part[1..2])
+}
+
+impl std::str::FromStr for HandRange {
+    type Err = ParseError;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let mut range = HandRange::new();
+        let parts = str.split(|ch: char| ch == ',' || ch.is_whitespace())
+            .filter(|part| !part.is_empty());
+
+        for part in parts {
+            let part = part.to_ascii_lowercase();
+            let (hands, weight) = match part.split_once(':') {
+                Some((hands, weight)) => {
+                    let weight = weight.parse::<f64>().map_err(|_| "Invalid weight in range")?;
+                    if !(0.0..=1.0).contains(&weight) {
+                        return Err("Range weights must be between 0 and 1");
+                    }
+                    (hands.to_string(), weight)
+                },
+                None => (part, 1.0)
+            };
+
+            if is_combo(&hands) {
+                range.add(parse_combo(&hands)?, weight);
+            } else {
+                for class in parse_classes(&hands)? {
+                    for cards in class_combos(class) {
+                        range.add(cards, weight);
+                    }
+                }
+            }
+        }
+
+        if range.is_empty() {
+            return Err("Empty range");
+        }
+        return Ok(range);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+
+    fn count(range: &str) -> usize {
+        range.parse::<HandRange>().unwrap().len()
+    }
+
+    #[test]
+    fn test_combo_counts() {
+        assert_eq!(count("AA"), 6);
+        assert_eq!(count("AKs"), 4);
+        assert_eq!(count("AKo"), 12);
+        assert_eq!(count("KA"), 16);
+        assert_eq!(count("QQ+"), 18);
+        assert_eq!(count("22+"), 78);
+        assert_eq!(count("KTo+"), 36);
+        assert_eq!(count("A5s-A2s"), 16);
+        assert_eq!(count("99-QQ"), 24);
+        assert_eq!(count("QQ+, AKs, A5s-A2s, KTo+"), 18 + 4 + 16 + 36);
+        assert_eq!(count("AhKh, KhAh"), 1);
+    }
+
+    #[test]
+    fn test_weights_and_duplicates() {
+        let range = "AKs:0.5, AK".parse::<HandRange>().unwrap();
+        assert_eq!(range.len(), 16);
+        assert_eq!(range.weighted_len(), 16.0);
+
+        let range = "AKs, KhAh:0.5".parse::<HandRange>().unwrap();
+        assert_eq!(range.len(), 4);
+        assert_eq!(range.weighted_len(), 3.5);
+        assert_eq!(range.combos[2].cards, CardVector::parse("Ah Kh").to_vec());
+    }
+
+    #[test]
+    fn test_dead_cards() {
+        let range = "AA, AKs".parse::<HandRange>().unwrap();
+        let dead = CardSet::from(&CardVector::parse("Ah Kc"));
+        assert_eq!(range.without(&dead).len(), 3 + 2);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        for range in ["", "AKx", "AAs", "AK:2", "AKs-QJs", "QQ-AKs", "AhAh", "A", "??"] {
+            assert!(range.parse::<HandRange>().is_err(), "{}", range);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Hold'em equity between hand ranges.
+//!
+//! Each seat holds one combo from its range. A deal of combos is played in
+//! proportion to the product of their weights, and only when no two seats
+//! share a card and no combo uses a board or dead card.
+
+use itertools::Itertools;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use super::card::{Card, CardSet};
+use super::deck::make_deck_without;
+use super::hand_rank_count::HandRankCount;
+use super::hold_em::find_winners;
+use super::monte_carlo::{for_each_trial, MonteCarloOptions};
+use super::deal::deal_count;
+use super::range::{Combo, HandRange};
+
+/// A sampled deal gives up after this many combo picks that share a card.
+const MAX_REJECTIONS: usize = 1000;
+
+/// Share of the pots won, with every pot counted by the weight of the deal
+/// that played it.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RangeResult {
+    pub equity: f64,
+    pub weight: f64
+}
+
+impl RangeResult {
+    pub fn new() -> RangeResult {
+        RangeResult {
+            equity: 0.0,
+            weight: 0.0
+        }
+    }
+
+    pub fn equity_pct(&self) -> f64 {
+        100.0 * self.equity / self.weight
+    }
+
+    fn add(&mut self, equity: f64, weight: f64) {
+        self.equity += equity;
+        self.weight += weight;
+    }
+}
+
+/// Equity for each seat, and for each of the hero's (the first seat's) combos.
+pub struct RangeEquity {
+    pub seats: Vec<RangeResult>,
+    pub hero_combos: Vec<(Combo, RangeResult)>
+}
+
+impl RangeEquity {
+    fn new(ranges: &[HandRange]) -> RangeEquity {
+        RangeEquity {
+            seats: vec![RangeResult::new(); ranges.len()],
+            hero_combos: ranges[0].combos.iter()
+                .map(|combo| (combo.clone(), RangeResult::new()))
+                .collect()
+        }
+    }
+
+    fn add(&mut self, deal: &[usize], weight: f64, shares: &[f64], pots: u64) {
+        let pots = weight * pots as f64;
+        for (seat, share) in shares.iter().enumerate() {
+            self.seats[seat].add(weight * share, pots);
+        }
+        self.hero_combos[deal[0]].1.add(weight * shares[0], pots);
+    }
+}
+
+fn live_ranges(ranges: &[HandRange], known: &CardSet) -> Vec<HandRange> {
+    ranges.iter()
+        .map(|range| range.without(known))
+        .collect()
+}
+
+fn known_cards(board: &[Card], dead: &[Card]) -> CardSet {
+    board.iter().chain(dead.iter()).collect()
+}
+
+fn pockets(ranges: &[HandRange], deal: &[usize]) -> Vec<Vec<Card>> {
+    deal.iter()
+        .enumerate()
+        .map(|(seat, index)| ranges[seat].combos[*index].cards.clone())
+        .collect()
+}
+
+/// Adds each winner's share of one pot.
+fn award_shares(shares: &mut [f64], winners: &[usize]) {
+    for winner in winners {
+        shares[*winner] += 1.0 / winners.len() as f64;
+    }
+}
+
+/// An upper bound on the number of boards played by `range_odds`, or `None`
+/// when it does not fit in a `u64`.
+pub fn range_deal_count(ranges: &[HandRange], board: &[Card], dead: &[Card]) -> Option<u64> {
+    let known = known_cards(board, dead);
+    let remaining = make_deck_without(&known).len().saturating_sub(2 * ranges.len());
+    let boards = deal_count(remaining, &[5 - board.len()])?;
+
+    live_ranges(ranges, &known).iter()
+        .try_fold(boards, |count, range| count.checked_mul(range.len() as u64))
+}
+
+fn add_deals(ranges: &[HandRange], seat: usize, used: CardSet, deal: &mut Vec<usize>, weight: f64, deals: &mut Vec<(Vec<usize>, f64)>) {
+    if seat == ranges.len() {
+        deals.push((deal.clone(), weight));
+        return;
+    }
+
+    for (index, combo) in ranges[seat].combos.iter().enumerate() {
+        let cards = combo.card_set();
+        if combo.weight > 0.0 && cards.is_disjoint(&used) {
+            deal.push(index);
+            add_deals(ranges, seat + 1, used | cards, deal, weight * combo.weight, deals);
+            deal.pop();
+        }
+    }
+}
+
+/// Every deal of one combo per seat without a shared card, with its weight.
+fn all_deals(ranges: &[HandRange]) -> Vec<(Vec<usize>, f64)> {
+    let mut deals = Vec::new();
+    add_deals(ranges, 0, CardSet::new(), &mut Vec::new(), 1.0, &mut deals);
+    return deals;
+}
+
+/// Plays every completion of `board` for one deal of pockets, returning each
+/// seat's share of the pots and the number of pots.
+fn play_boards(pockets: &[Vec<Card>], board: &[Card], deck: &[Card]) -> (Vec<f64>, u64) {
+    let used = pockets.iter().flatten().collect::<CardSet>();
+    let mut hand_rank_counts = HandRankCount::for_players(pockets.len());
+    let mut shares = vec![0.0; pockets.len()];
+    let mut pots = 0;
+
+    for run_out in deck.iter().filter(|card| !used.contains(card)).combinations(5 - board.len()) {
+        let complete_board = board.iter().chain(run_out).collect::<Vec<_>>();
+        award_shares(&mut shares, &find_winners(pockets, &complete_board, &mut hand_rank_counts));
+        pots += 1;
+    }
+
+    return (shares, pots);
+}
+
+/// Exhaustive equity between hold'em `ranges`, playing every deal of combos
+/// on every completion of `board`. Deals are striped across `threads`, and
+/// the results do not depend on the number of threads.
+pub fn range_odds(ranges: &[HandRange], board: &[Card], dead: &[Card], threads: usize) -> RangeEquity {
+    let known = known_cards(board, dead);
+    let ranges = live_ranges(ranges, &known);
+    let deck = make_deck_without(&known);
+    let deals = all_deals(&ranges);
+    let threads = threads.max(1);
+
+    let stripes = std::thread::scope(|scope| {
+        let handles = (0..threads)
+            .map(|stripe| {
+                let (ranges, deck, deals) = (&ranges, &deck, &deals);
+                scope.spawn(move || {
+                    deals.iter()
+                        .skip(stripe)
+                        .step_by(threads)
+                        .map(|(deal, _weight)| play_boards(&pockets(ranges, deal), board, deck))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    // Merge in deal order so the floating point sums are reproducible
+    let mut results = RangeEquity::new(&ranges);
+    for (index, (deal, weight)) in deals.iter().enumerate() {
+        let (shares, pots) = &stripes[index % threads][index / threads];
+        results.add(deal, *weight, shares, *pots);
+    }
+
+    return results;
+}
+
+fn pick_combo<R: Rng>(rng: &mut R, cumulative_weights: &[f64]) -> usize {
+    let total = cumulative_weights.last().cloned().unwrap_or(0.0);
+    let x = rng.gen_range(0.0, total);
+    cumulative_weights.partition_point(|weight| *weight <= x)
+}
+
+/// Picks one combo per seat by weight, retrying until no two seats share a card.
+fn pick_deal<R: Rng>(rng: &mut R, ranges: &[HandRange], cumulative_weights: &[Vec<f64>]) -> Option<Vec<usize>> {
+    'retry: for _attempt in 0..MAX_REJECTIONS {
+        let mut used = CardSet::new();
+        let mut deal = Vec::with_capacity(ranges.len());
+        for (range, weights) in ranges.iter().zip(cumulative_weights) {
+            let index = pick_combo(rng, weights);
+            let cards = range.combos[index].card_set();
+            if !cards.is_disjoint(&used) {
+                continue 'retry;
+            }
+            used |= cards;
+            deal.push(index);
+        }
+        return Some(deal);
+    }
+    return None;
+}
+
+/// Sampled equity between hold'em `ranges`: each trial picks one combo per
+/// seat by weight and a random completion of `board`. A trial that keeps
+/// picking combos sharing a card is skipped.
+pub fn range_monte_carlo(ranges: &[HandRange], board: &[Card], dead: &[Card], options: &MonteCarloOptions) -> RangeEquity {
+    let known = known_cards(board, dead);
+    let ranges = live_ranges(ranges, &known);
+    let mut deck = make_deck_without(&known);
+    let mut results = RangeEquity::new(&ranges);
+
+    let cumulative_weights = ranges.iter()
+        .map(|range| range.combos.iter()
+             .scan(0.0, |total, combo| {
+                 *total += combo.weight;
+                 Some(*total)
+             })
+             .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    if cumulative_weights.iter().any(|weights| weights.last().is_none_or(|total| *total <= 0.0)) {
+        return results;
+    }
+
+    let run_out = 5 - board.len();
+    let mut hand_rank_counts = HandRankCount::for_players(ranges.len());
+    for_each_trial(options, |rng| {
+        if let Some(deal) = pick_deal(rng, &ranges, &cumulative_weights) {
+            let pockets = pockets(&ranges, &deal);
+            let used = pockets.iter().flatten().collect::<CardSet>();
+
+            // The first cards of a shuffle that are not in a pocket are a
+            // random run-out from the rest of the deck
+            let (drawn, _rest) = deck.partial_shuffle(rng, run_out + 2 * ranges.len());
+            let complete_board = board.iter()
+                .chain(drawn.iter().filter(|card| !used.contains(card)).take(run_out))
+                .collect::<Vec<_>>();
+
+            let mut shares = vec![0.0; ranges.len()];
+            award_shares(&mut shares, &find_winners(&pockets, &complete_board, &mut hand_rank_counts));
+            results.add(&deal, 1.0, &shares, 1);
+        }
+    });
+
+    return results;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+
+    fn ranges(ranges: &[&str]) -> Vec<HandRange> {
+        ranges.iter()
+            .map(|range| range.parse::<HandRange>().unwrap())
+            .collect()
+    }
+
+    fn odds(villains: &[&str], board: &str, threads: usize) -> RangeEquity {
+        range_odds(&ranges(villains), &CardVector::parse(board), &[], threads)
+    }
+
+    #[test]
+    fn test_single_combos_match_hold_em_odds() {
+        let results = odds(&["AcAd", "KcKd"], "2s 7h 9d Jc", 1);
+        assert_eq!(results.seats[0].weight, 44.0);
+        assert_eq!(results.seats[1].equity, 2.0);
+        assert_eq!(results.hero_combos.len(), 1);
+        assert_eq!(results.hero_combos[0].1, results.seats[0]);
+    }
+
+    #[test]
+    fn test_card_removal() {
+        let results = odds(&["AA", "AhKh"], "2s 7c 9d Jc", 2);
+        assert_eq!(results.hero_combos.len(), 6);
+
+        // Only the three aces without the Ah are ever dealt
+        let dealt = results.hero_combos.iter()
+            .filter(|(_combo, result)| result.weight > 0.0)
+            .count();
+        assert_eq!(dealt, 3);
+        assert_eq!(results.seats[0].weight, 3.0 * 44.0);
+
+        let dead = CardVector::parse("Ad");
+        let results = range_odds(&ranges(&["AA", "AhKh"]), &CardVector::parse("2s 7c 9d Jc"), &dead, 1);
+        assert_eq!(results.hero_combos.len(), 3);
+        assert_eq!(results.seats[1].weight, 43.0);
+    }
+
+    #[test]
+    fn test_weights_and_total_equity() {
+        let results = odds(&["QQ+, AKs:0.5", "JJ, AQs"], "2c 7d 9h Ts", 3);
+        let total = results.seats.iter().map(|seat| seat.equity).sum::<f64>();
+        assert!((total - results.seats[0].weight).abs() < 1e-6);
+
+        let single = odds(&["QQ+, AKs:0.5", "JJ, AQs"], "2c 7d 9h Ts", 1);
+        assert_eq!(results.seats, single.seats);
+
+        // Each AKs combo counts for half
+        let aks = results.hero_combos.iter()
+            .find(|(combo, _result)| combo.cards == CardVector::parse("Ac Kc").to_vec())
+            .unwrap();
+        assert_eq!(aks.0.weight, 0.5);
+    }
+
+    #[test]
+    fn test_monte_carlo_matches_enumeration() {
+        let ranges = ranges(&["AA, KK", "QQ+, AK"]);
+        let board = CardVector::parse("2c 7d 9h");
+        let exact = range_odds(&ranges, &board, &[], 2);
+        let sampled = range_monte_carlo(&ranges, &board, &[], &MonteCarloOptions::new(20000).with_seed(3));
+
+        assert_eq!(sampled.seats[0].weight, 20000.0);
+        assert!((exact.seats[0].equity_pct() - sampled.seats[0].equity_pct()).abs() < 1.5);
+    }
+}
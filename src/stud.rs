@@ -0,0 +1,494 @@
+//! Seven Card Stud and Stud Hi/Lo equity.
+//!
+//! Each player holds some face down and face up cards and is dealt the rest
+//! of a seven card hand from the deck. Cards folded by other players are
+//! passed as `dead` and never dealt. Wild card rules see a player's down
+//! cards as the hole cards and the up cards as the board. Up card rules see
+//! the table's up cards street by street, each street dealt in player
+//! order, with each player's known up cards taken to be in the order dealt.
+
+use std::cmp::Ordering;
+
+use super::card::{Card, CardSet, CardVector, fmt_cards};
+use super::deck::make_deck_without;
+use super::fast_eval;
+use super::poker_hand::{PokerHand, HandRank};
+use super::hand_rank_count::HandRankCount;
+use super::monte_carlo::MonteCarloOptions;
+use super::deal::{deal_count, for_each_deal, for_each_random_deal};
+use super::pot_equity::{PotEquity, award_pot};
+use super::wild::WildCards;
+
+type ParseError = &'static str;
+
+pub const STUD_HAND_SIZE: usize = 7;
+const STUD_UP_CARDS: usize = 4;
+
+/// One player's known cards. Parsed from `down/up`, e.g. `AcKd/Qh`, with the
+/// up cards optional.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StudHand {
+    pub down: Vec<Card>,
+    pub up: Vec<Card>
+}
+
+impl StudHand {
+    pub fn new(down: Vec<Card>, up: Vec<Card>) -> StudHand {
+        StudHand {
+            down: down,
+            up: up
+        }
+    }
+
+    pub fn cards(&self) -> Vec<Card> {
+        self.down.iter().chain(self.up.iter()).cloned().collect()
+    }
+
+    /// Number of cards still to be dealt to this hand.
+    pub fn cards_needed(&self) -> usize {
+        STUD_HAND_SIZE - self.down.len() - self.up.len()
+    }
+
+    /// Number of the cards still to be dealt that come face up. A complete
+    /// hand has three down cards and four up cards.
+    pub fn up_cards_needed(&self) -> usize {
+        STUD_UP_CARDS.saturating_sub(self.up.len()).min(self.cards_needed())
+    }
+}
+
+impl std::str::FromStr for StudHand {
+    type Err = ParseError;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let (down, up) = match str.split_once('/') {
+            Some((down, up)) => (down, up),
+            None => (str, "")
+        };
+
+        let hand = StudHand::new(down.parse::<CardVector>()?.0, up.parse::<CardVector>()?.0);
+        if hand.down.len() + hand.up.len() > STUD_HAND_SIZE {
+            return Err("A stud hand has at most seven cards");
+        }
+        return Ok(hand);
+    }
+}
+
+impl std::fmt::Display for StudHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{}]", fmt_cards(&self.down))?;
+        if !self.up.is_empty() {
+            write!(f, " {}", fmt_cards(&self.up))?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that there are at least two hands, that no card appears twice,
+/// that no more jokers are dealt than `wild` adds to the deck and that the
+/// deck can complete every hand. Hands are dealt as card sets, so at most
+/// one joker is supported. Up card rules need each up card's street, so
+/// with them a hand has at most four up cards.
+pub fn validate_stud_hands(hands: &[StudHand], dead: &[Card], wild: &WildCards) -> Result<(), ParseError> {
+    if hands.len() < 2 {
+        return Err("Stud needs at least two hands");
+    }
+
+    if wild.jokers > 1 {
+        return Err("Stud supports at most one joker");
+    }
+
+    if wild.has_up_card_rules() && hands.iter().any(|hand| hand.up.len() > STUD_UP_CARDS) {
+        return Err("A stud hand has at most four up cards");
+    }
+
+    let known = hands.iter()
+        .flat_map(|hand| hand.cards())
+        .chain(dead.iter().cloned())
+        .collect::<Vec<_>>();
+
+    if known.iter().collect::<CardSet>().len() != known.len() {
+        return Err("The same card appears more than once");
+    }
+
+    if known.iter().filter(|card| Card::is_joker(card)).count() > wild.jokers {
+        return Err("More jokers dealt than there are in the deck");
+    }
+
+    let needed = hands.iter().map(|hand| hand.cards_needed()).sum::<usize>();
+    if needed > 52 + wild.jokers - known.len() {
+        return Err("Not enough cards left in the deck to complete every hand");
+    }
+
+    return Ok(());
+}
+
+/// Number of distinct ways to complete every hand, or `None` when it does
+/// not fit in a `u64`.
+pub fn stud_deal_count(hands: &[StudHand], dead: &[Card], wild: &WildCards) -> Option<u64> {
+    let (_known, needed) = stud_deal(hands, wild);
+    return deal_count(stud_deck(hands, dead, wild).len(), &needed);
+}
+
+fn stud_deck(hands: &[StudHand], dead: &[Card], wild: &WildCards) -> Vec<Card> {
+    let known = hands.iter()
+        .flat_map(|hand| hand.cards())
+        .chain(dead.iter().cloned())
+        .collect::<Vec<_>>();
+    let mut deck = make_deck_without(&CardSet::from(known.as_slice()));
+    wild.add_jokers(&mut deck, &known);
+    return deck;
+}
+
+/// The known cards and the number still needed for each hand, in the form
+/// taken by `deal::for_each_deal`. Hand dependent wild rules need to know
+/// which cards are face down, so with them each hand is dealt as two, its
+/// down cards and then its up cards. Up card rules also need the order the
+/// up cards come in, so with them each hand is dealt as its down cards and
+/// then one up card per street.
+fn stud_deal(hands: &[StudHand], wild: &WildCards) -> (Vec<CardSet>, Vec<usize>) {
+    let mut known = Vec::new();
+    let mut needed = Vec::new();
+    for hand in hands {
+        if wild.has_up_card_rules() {
+            known.push(CardSet::from(hand.down.as_slice()));
+            needed.push(hand.cards_needed() - hand.up_cards_needed());
+            for street in 0..STUD_UP_CARDS {
+                match hand.up.get(street) {
+                    Some(card) => {
+                        known.push(CardSet::from(std::slice::from_ref(card)));
+                        needed.push(0);
+                    },
+                    None => {
+                        known.push(CardSet::new());
+                        needed.push(usize::from(street < hand.up.len() + hand.up_cards_needed()));
+                    }
+                }
+            }
+        } else if wild.has_hand_rules() {
+            known.push(CardSet::from(hand.down.as_slice()));
+            known.push(CardSet::from(hand.up.as_slice()));
+            needed.push(hand.cards_needed() - hand.up_cards_needed());
+            needed.push(hand.up_cards_needed());
+        } else {
+            known.push(hand.cards().iter().collect());
+            needed.push(hand.cards_needed());
+        }
+    }
+    return (known, needed);
+}
+
+/// Each player's complete hand and the cards wild for it, from a deal laid
+/// out by `stud_deal`.
+fn complete_hands(dealt: &[CardSet], wild: &WildCards) -> Vec<(CardSet, CardSet)> {
+    if wild.has_up_card_rules() {
+        let hands = dealt.chunks(1 + STUD_UP_CARDS).collect::<Vec<_>>();
+        let up_cards = (1..=STUD_UP_CARDS)
+            .flat_map(|street| hands.iter().flat_map(move |slots| slots[street].to_vec()))
+            .collect::<Vec<_>>();
+        let table_wild = wild.for_up_cards(&up_cards.iter().collect::<Vec<_>>());
+
+        return hands.iter()
+            .map(|slots| {
+                let up = slots[1..].iter().flat_map(|slot| slot.to_vec()).collect::<Vec<_>>();
+                let cards = slots.iter().fold(CardSet::new(), |cards, slot| cards | *slot);
+                (cards, table_wild | wild.for_hand(&slots[0].to_vec(), &up.iter().collect::<Vec<_>>()))
+            })
+            .collect();
+    }
+
+    if !wild.has_hand_rules() {
+        return dealt.iter().map(|cards| (*cards, wild.cards)).collect();
+    }
+
+    dealt.chunks(2)
+        .map(|down_up| {
+            let up = down_up[1].to_vec();
+            let wild_cards = wild.for_hand(&down_up[0].to_vec(), &up.iter().collect::<Vec<_>>());
+            (down_up[0] | down_up[1], wild_cards)
+        })
+        .collect()
+}
+
+fn score_high(cards: CardSet, wild_cards: CardSet) -> (HandRank, i32) {
+    if cards.is_disjoint(&wild_cards) && fast_eval::can_evaluate(&cards) {
+        let score = fast_eval::evaluate(cards);
+        return (fast_eval::hand_rank(score), score);
+    }
+
+    let hand = PokerHand::build_with(&cards.to_vec().iter().collect::<Vec<_>>(), &|card: &Card| wild_cards.contains(card));
+    return (hand.rank, hand.score);
+}
+
+fn award_deal(dealt: &[CardSet], wild: &WildCards, hi_lo: bool, results: &mut [PotEquity], hand_rank_counts: &mut [HandRankCount]) {
+    let complete_hands = complete_hands(dealt, wild);
+
+    let mut high_winners = Vec::new();
+    let mut best_score = None;
+    for (index, (cards, wild_cards)) in complete_hands.iter().enumerate() {
+        let (rank, score) = score_high(*cards, *wild_cards);
+        hand_rank_counts[index].inc(rank);
+
+        match best_score.map(|max| score.cmp(&max)) {
+            Some(Ordering::Less) => {},
+            Some(Ordering::Equal) => high_winners.push(index),
+            _ => {
+                high_winners.clear();
+                high_winners.push(index);
+                best_score = Some(score);
+            }
+        }
+    }
+
+    let mut low_winners = Vec::new();
+    if hi_lo {
+        let mut best_low = None;
+        for (index, (cards, _wild_cards)) in complete_hands.iter().enumerate() {
+            if let Some(score) = fast_eval::evaluate_low_eight(*cards) {
+                if best_low.is_none_or(|min| score < min) {
+                    low_winners.clear();
+                    best_low = Some(score);
+                }
+                if best_low == Some(score) {
+                    low_winners.push(index);
+                }
+            }
+        }
+    }
+
+    award_pot(results, &high_winners, &low_winners);
+}
+
+fn merge(stripes: Vec<(Vec<PotEquity>, Vec<HandRankCount>)>, players: usize, hand_rank_counts: &mut [HandRankCount]) -> Vec<PotEquity> {
+    let mut results = vec![PotEquity::new(); players];
+    for (stripe_results, stripe_counts) in stripes {
+        for (result, stripe_result) in results.iter_mut().zip(stripe_results) {
+            *result += stripe_result;
+        }
+        for (count, stripe_count) in hand_rank_counts.iter_mut().zip(stripe_counts.iter()) {
+            *count += stripe_count;
+        }
+    }
+    return results;
+}
+
+/// Exhaustive equity for Seven Card Stud, or Stud Hi/Lo (eight-or-better)
+/// when `hi_lo` is set. Cards in `wild` are wild for the high hand only.
+pub fn stud_odds(hands: &[StudHand], dead: &[Card], wild: &WildCards, hi_lo: bool, hand_rank_counts: &mut [HandRankCount], threads: usize) -> Vec<PotEquity> {
+    let deck = stud_deck(hands, dead, wild);
+    let (known, needed) = stud_deal(hands, wild);
+
+    let stripes = for_each_deal(&deck, &known, &needed, threads,
+        || (vec![PotEquity::new(); hands.len()], HandRankCount::for_players(hands.len())),
+        |(results, counts), dealt| award_deal(dealt, wild, hi_lo, results, counts));
+
+    return merge(stripes, hands.len(), hand_rank_counts);
+}
+
+/// Sampled equity for Seven Card Stud or Stud Hi/Lo.
+pub fn stud_monte_carlo(hands: &[StudHand], dead: &[Card], wild: &WildCards, hi_lo: bool, hand_rank_counts: &mut [HandRankCount], options: &MonteCarloOptions) -> Vec<PotEquity> {
+    let mut deck = stud_deck(hands, dead, wild);
+    let (known, needed) = stud_deal(hands, wild);
+    let mut results = vec![PotEquity::new(); hands.len()];
+
+    for_each_random_deal(&mut deck, &known, &needed, options, |dealt| {
+        award_deal(dealt, wild, hi_lo, &mut results, hand_rank_counts);
+    });
+
+    return results;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hands(strs: &[&str]) -> Vec<StudHand> {
+        strs.iter().map(|str| str.parse().unwrap()).collect()
+    }
+
+    fn odds(strs: &[&str], dead: &str, hi_lo: bool, threads: usize) -> (Vec<PotEquity>, Vec<HandRankCount>) {
+        wild_odds(strs, dead, &WildCards::new(), hi_lo, threads)
+    }
+
+    fn wild_odds(strs: &[&str], dead: &str, wild: &WildCards, hi_lo: bool, threads: usize) -> (Vec<PotEquity>, Vec<HandRankCount>) {
+        let hands = hands(strs);
+        let dead = CardVector::parse(dead).to_vec();
+        let mut hand_rank_counts = HandRankCount::for_players(hands.len());
+        let results = stud_odds(&hands, &dead, wild, hi_lo, &mut hand_rank_counts, threads);
+        return (results, hand_rank_counts);
+    }
+
+    #[test]
+    fn test_parse_stud_hand() {
+        let hand = "AcKd/Qh 7s".parse::<StudHand>().unwrap();
+        assert_eq!(hand.down, CardVector::parse("AcKd").to_vec());
+        assert_eq!(hand.up, CardVector::parse("Qh7s").to_vec());
+        assert_eq!(hand.cards_needed(), 3);
+        assert_eq!(hand.to_string(), "[A♣ K♦] Q♥ 7♠");
+
+        assert!("AcKd".parse::<StudHand>().unwrap().up.is_empty());
+        assert!("AcKd/QhJh9h8h7h2c".parse::<StudHand>().is_err());
+        assert!("AcZd/Kh".parse::<StudHand>().is_err());
+        assert!("AcKd/Kx".parse::<StudHand>().is_err());
+    }
+
+    #[test]
+    fn test_seventh_street() {
+        let (results, counts) = odds(&["AcAd/AhKs9c8d2h", "KcKd/Qs Qh 5c 5d 3s"], "", false, 1);
+        assert_eq!(results[0].pots, 1);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(counts[0][HandRank::Triplets as usize], 1);
+        assert_eq!(counts[1][HandRank::TwoPair as usize], 1);
+    }
+
+    #[test]
+    fn test_sixth_street_deal_count() {
+        let strs = ["AcAd/AhKs9c8d", "KcKd/Qs Qh 5c 5d"];
+        assert_eq!(stud_deal_count(&hands(&strs), &[], &WildCards::new()), Some(40 * 39));
+
+        let (results, _counts) = odds(&strs, "", false, 1);
+        assert_eq!(results[0].pots, 40 * 39);
+    }
+
+    #[test]
+    fn test_dead_cards_are_not_dealt() {
+        let strs = ["AcAd/AhJs9c8d", "KcKd/Qs Qh 5c 5d"];
+        assert_eq!(stud_deal_count(&hands(&strs), &CardVector::parse("Ks Kh 2c"), &WildCards::new()), Some(37 * 36));
+
+        // With both kings folded only the last two queens and fives fill up the second player
+        let (_results, counts) = odds(&strs, "Ks Kh 2c", false, 1);
+        assert_eq!(counts[1][HandRank::FullHouse as usize], 4 * 36);
+    }
+
+    #[test]
+    fn test_threaded_matches_single_thread() {
+        let strs = ["AcAd/AhKs9c8s", "KcKd/Qs Qh 5c 8d", "7h6h/5h 4h 3c 2d"];
+        let (single, single_counts) = odds(&strs, "2h", true, 1);
+        let (threaded, threaded_counts) = odds(&strs, "2h", true, 4);
+        for i in 0..strs.len() {
+            assert_eq!(single[i].pots, threaded[i].pots);
+            assert_eq!(single[i].scoops, threaded[i].scoops);
+            assert_eq!(single[i].high.wins, threaded[i].high.wins);
+            assert_eq!(single[i].low.wins, threaded[i].low.wins);
+            assert_eq!(*single_counts[i], *threaded_counts[i]);
+        }
+    }
+
+    #[test]
+    fn test_hi_lo_split_and_scoop() {
+        let (results, _counts) = odds(&["AcAd/AhKsKd9c9h", "2c3d/4h5s8d Qc Jc"], "", true, 1);
+        assert_eq!(results[0].equity, 0.5);
+        assert_eq!(results[1].equity, 0.5);
+
+        let (results, _counts) = odds(&["2c3d/4h5s6d Qc Jc", "KcKh/QsJd9d8h7h"], "", true, 1);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(results[0].scoops, 1);
+    }
+
+    #[test]
+    fn test_validate_stud_hands() {
+        assert!(validate_stud_hands(&hands(&["AcAd/Ah", "KcKd/Qs"]), &[], &WildCards::new()).is_ok());
+        assert!(validate_stud_hands(&hands(&["AcAd/Ah", "AcKd/Qs"]), &[], &WildCards::new()).is_err());
+        assert!(validate_stud_hands(&hands(&["AcAd/Ah", "KcKd/Qs"]), &CardVector::parse("Ac"), &WildCards::new()).is_err());
+        assert!(validate_stud_hands(&hands(&["AcAd/Ah"]), &[], &WildCards::new()).is_err());
+        assert!(validate_stud_hands(&[], &[], &WildCards::new()).is_err());
+
+        let deck = make_deck_without(&CardSet::new());
+        let dead = deck[..46].to_vec();
+        let hands = vec![StudHand::new(deck[46..48].to_vec(), vec![]), StudHand::new(deck[48..50].to_vec(), vec![])];
+        assert!(validate_stud_hands(&hands, &dead, &WildCards::new()).is_err());
+    }
+
+    #[test]
+    fn test_baseball() {
+        let strs = ["3c Ad Kh/9s Ah 2c 7d", "Kc Kd Qh/Ks Qs Jc 5d"];
+        let (results, counts) = wild_odds(&strs, "", &WildCards::new().with_named_rule("baseball").unwrap(), false, 1);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(counts[0][HandRank::Quads as usize], 1);
+
+        let (results, _counts) = odds(&strs, "", false, 1);
+        assert_eq!(results[1].equity, 1.0);
+    }
+
+    #[test]
+    fn test_low_hole_card() {
+        let wild = WildCards::new().with_named_rule("low-hole").unwrap();
+
+        // The kings are wild for the first player, not the deuce showing,
+        // and the nines for the second
+        let (results, counts) = wild_odds(&["Ac Ad Kh/2s 5h Kc 7d", "Tc Td 9h/8s 8h 4c 3d"], "", &wild, false, 1);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(counts[0][HandRank::Quads as usize], 1);
+        assert_eq!(counts[1][HandRank::FullHouse as usize], 1);
+
+        // The river comes face down and is the first player's low hole card,
+        // making quad aces when it pairs a card showing or is an ace
+        let strs = ["Ac Ad/2s 5h Kc 7d", "Tc Td/8s 8h 4c 3d"];
+        assert_eq!(stud_deal_count(&hands(&strs), &[], &wild), stud_deal_count(&hands(&strs), &[], &WildCards::new()));
+        let (_results, counts) = wild_odds(&strs, "", &wild, false, 1);
+        assert_eq!(counts[0][HandRank::Quads as usize], (4 * 3 + 2) * 39);
+        assert_eq!(counts[0][HandRank::FiveOfAKind as usize], 0);
+    }
+
+    #[test]
+    fn test_follow_the_queen() {
+        let wild = WildCards::new().with_named_rule("follow-the-queen").unwrap();
+
+        // The queen is followed by the first player's 5h, making trip aces
+        let (results, counts) = wild_odds(&["Ac Ad 3h/9c 5h 7d 6s", "Kc Kd 2h/Qs 8s 4c Js"], "", &wild, false, 1);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(counts[0][HandRank::Triplets as usize], 1);
+
+        // Dealt last, the queen is the only wild card
+        let (results, counts) = wild_odds(&["Ac Ad 3h/9c 5h 7d 6s", "Kc Kd 2h/8s 4c Js Qs"], "", &wild, false, 1);
+        assert_eq!(results[1].equity, 1.0);
+        assert_eq!(counts[0][HandRank::OnePair as usize], 1);
+
+        // Each up card still to come is dealt to its own street
+        let strs = ["Ac Ad 3h/9c 5h 7d", "Kc Kd 2h/Qs 8s 4c"];
+        let (known, needed) = stud_deal(&hands(&strs), &wild);
+        assert_eq!(needed, vec![0, 0, 0, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(known[6], CardSet::from(&CardVector::parse("Qs")));
+        assert_eq!(stud_deal_count(&hands(&strs), &[], &wild), Some(40 * 39));
+        let (results, _counts) = wild_odds(&strs, "", &wild, false, 2);
+        assert_eq!(results[0].pots, 40 * 39);
+
+        let strs = ["Ac Ad/9c 5h 7d 6s 2c", "Kc Kd 2h/Qs 8s 4c"];
+        assert!(validate_stud_hands(&hands(&strs), &[], &wild).is_err());
+        assert!(validate_stud_hands(&hands(&strs), &[], &WildCards::new()).is_ok());
+    }
+
+    #[test]
+    fn test_down_and_up_cards_are_dealt_apart() {
+        let wild = WildCards::new().with_hand_rule(crate::wild::LowHoleCard);
+        let (known, needed) = stud_deal(&hands(&["AcAd/Kh", "Qc Qd/Qh Js Jh 9c"]), &wild);
+        assert_eq!(needed, vec![1, 3, 1, 0]);
+        assert_eq!(known[2], CardSet::from(&CardVector::parse("Qc Qd")));
+
+        let (_known, needed) = stud_deal(&hands(&["AcAd/Kh", "Qc Qd/Qh Js Jh 9c"]), &WildCards::new());
+        assert_eq!(needed, vec![4, 1]);
+    }
+
+    #[test]
+    fn test_joker() {
+        let wild = WildCards::new().with_jokers(1);
+        let (results, counts) = wild_odds(&["?? Ad Kh/As 5h Kc 7d", "Kd Qd 9h/Ks Qs Qc 5d"], "", &wild, false, 1);
+        assert_eq!(results[0].equity, 1.0);
+        assert_eq!(counts[0][HandRank::FullHouse as usize], 1);
+
+        assert!(validate_stud_hands(&hands(&["?? Ad/Kh", "Kd Qd/9h"]), &[], &WildCards::new()).is_err());
+        assert!(validate_stud_hands(&hands(&["?? Ad/Kh", "Kd Qd/9h"]), &[], &WildCards::new().with_jokers(2)).is_err());
+        assert_eq!(stud_deal_count(&hands(&["Ac Ad/Kh Qh Jh 9h", "Kd Qd/9c 2c 3c 4c"]), &[], &wild), Some(41 * 40));
+    }
+
+    #[test]
+    fn test_monte_carlo_converges_to_enumeration() {
+        let hands = hands(&["AcAd/AhKs9c", "KcKd/Qs Qh 5c"]);
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let exact = stud_odds(&hands, &[], &WildCards::new(), false, &mut hand_rank_counts, 1);
+
+        let mut hand_rank_counts = HandRankCount::for_players(2);
+        let sampled = stud_monte_carlo(&hands, &[], &WildCards::new(), false, &mut hand_rank_counts, &MonteCarloOptions::new(3000).with_seed(12));
+        assert_eq!(sampled[0].pots, 3000);
+        let (low, high) = sampled[0].high.win_confidence_interval(4.0);
+        assert!(low <= exact[0].high.win_pct() && exact[0].high.win_pct() <= high);
+    }
+}
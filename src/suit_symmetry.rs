@@ -0,0 +1,99 @@
+//! Suit permutations that leave the known cards of a deal unchanged.
+//!
+//! Swapping suits does not change how hands compare, so two run-outs related
+//! by a permutation that maps every pocket, the board, the deck and the wild
+//! cards onto themselves have exactly the same outcome. Enumeration can
+//! visit one run-out from each class and count it once per run-out in the
+//! class. Preflop with offsuit or same-suited pockets most suits are
+//! interchangeable and the classes are large.
+
+use itertools::Itertools;
+
+use super::card::CardSet;
+
+/// Bits of the clubs in a `CardSet`. Cards are indexed rank-major, so the
+/// diamonds, hearts and spades are the same bits shifted by 1, 2 and 3.
+const CLUBS: u64 = 0x1_1111_1111_1111;
+
+/// Maps suit `i`, in `Suit` order, to suit `self.0[i]`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SuitPermutation([usize; 4]);
+
+impl SuitPermutation {
+    pub fn identity() -> SuitPermutation {
+        SuitPermutation([0, 1, 2, 3])
+    }
+
+    /// The image of `cards`. Jokers have no suit and are left alone.
+    pub fn apply(&self, cards: CardSet) -> CardSet {
+        let bits = cards.bits();
+        let mut result = bits & !CardSet::standard_deck().bits();
+        for (from, to) in self.0.iter().enumerate() {
+            let suited = bits & (CLUBS << from);
+            result |= if *to >= from { suited << (to - from) } else { suited >> (from - to) };
+        }
+        return CardSet::from_bits(result);
+    }
+}
+
+/// The suit permutations mapping each of the `fixed` sets onto itself.
+/// They form a group, always including the identity.
+pub fn symmetries(fixed: &[CardSet]) -> Vec<SuitPermutation> {
+    (0..4).permutations(4)
+        .map(|suits| SuitPermutation([suits[0], suits[1], suits[2], suits[3]]))
+        .filter(|permutation| fixed.iter().all(|cards| permutation.apply(*cards) == *cards))
+        .collect()
+}
+
+/// The `symmetries` that leave `cards` unchanged, or `None` when one of them
+/// maps it to a lower `CardSet` value. Permutations keep each card's rank,
+/// so when `cards` holds only ranks from some rank up this decides the
+/// comparison of every set with those top ranks.
+pub fn stabilizer(cards: CardSet, symmetries: &[SuitPermutation]) -> Option<Vec<SuitPermutation>> {
+    let mut unchanged = Vec::with_capacity(symmetries.len());
+    for permutation in symmetries {
+        let image = permutation.apply(cards).bits();
+        if image < cards.bits() {
+            return None;
+        }
+        if image == cards.bits() {
+            unchanged.push(*permutation);
+        }
+    }
+    return Some(unchanged);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+
+    fn set(cards: &str) -> CardSet {
+        CardSet::from(&CardVector::parse(cards))
+    }
+
+    #[test]
+    fn test_apply() {
+        let swap = SuitPermutation([1, 0, 3, 2]);
+        assert_eq!(swap.apply(set("Ac Kd 2h 3s ??")), set("Ad Kc 2s 3h ??"));
+        assert_eq!(SuitPermutation([3, 2, 1, 0]).apply(set("Ac Ts")), set("As Tc"));
+        assert_eq!(SuitPermutation::identity().apply(set("Ac Kd")), set("Ac Kd"));
+    }
+
+    #[test]
+    fn test_symmetries() {
+        assert_eq!(symmetries(&[]).len(), 24);
+        assert_eq!(symmetries(&[set("As Ks"), set("Qs Js")]).len(), 6);
+        assert_eq!(symmetries(&[set("Ac Ad"), set("Kc Kd")]).len(), 4);
+        assert_eq!(symmetries(&[set("Ac Kd"), set("Qh Js")]), vec![SuitPermutation::identity()]);
+    }
+
+    #[test]
+    fn test_stabilizer() {
+        let symmetries = symmetries(&[set("Ac Ad"), set("Kc Kd")]);
+        assert_eq!(stabilizer(set("Qc Qd"), &symmetries).unwrap().len(), 4);
+        assert_eq!(stabilizer(set("Qc Qh"), &symmetries).unwrap().len(), 1);
+        assert_eq!(stabilizer(set("Qd Jh"), &symmetries), None);
+        assert_eq!(stabilizer(set("Qd Qs"), &symmetries), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! Wild card rules for the equity calculators.
+
+use std::sync::Arc;
+
+use strum::IntoEnumIterator;
+
+use super::card::{Suit, Rank, Card, CardSet, IsWildCard};
+use super::deck::make_deck_with_jokers;
+use super::poker_hand::PokerHand;
+
+type ParseError = &'static str;
+
+fn cards_matching(is_wild: IsWildCard) -> CardSet {
+    make_deck_with_jokers(1).iter()
+        .filter(|card| is_wild(card))
+        .collect()
+}
+
+/// A wild card rule that depends on the hand being scored, such as "the
+/// lowest hole card is wild". Returns the cards that are wild for the
+/// player holding `hole` and playing with `board`, which in stud are the
+/// player's down and up cards. Rules that depend on the order the cards
+/// were dealt around the table are an `UpCardRule` instead.
+pub trait WildRule: Send + Sync {
+    fn wild_cards(&self, hole: &[Card], board: &[&Card]) -> CardSet;
+}
+
+impl<F> WildRule for F
+where F: Fn(&[Card], &[&Card]) -> CardSet + Send + Sync {
+    fn wild_cards(&self, hole: &[Card], board: &[&Card]) -> CardSet {
+        self(hole, board)
+    }
+}
+
+/// The lowest ranked hole card is wild for its holder, along with every
+/// other card of that rank. Aces are high and jokers are ignored.
+pub struct LowHoleCard;
+
+impl WildRule for LowHoleCard {
+    fn wild_cards(&self, hole: &[Card], _board: &[&Card]) -> CardSet {
+        match hole.iter().filter(|card| !Card::is_joker(card)).map(|card| card.rank).min() {
+            Some(rank) => cards_of_rank(rank),
+            None => CardSet::new()
+        }
+    }
+}
+
+/// A stud wild card rule that depends on every player's face up cards in
+/// the order they were dealt, street by street, such as Follow the Queen.
+/// Returns the cards that are wild for every player.
+pub trait UpCardRule: Send + Sync {
+    fn wild_cards(&self, up_cards: &[&Card]) -> CardSet;
+}
+
+fn cards_of_rank(rank: Rank) -> CardSet {
+    Suit::iter().take(4).map(|suit| Card::new(rank, suit)).collect()
+}
+
+/// Queens are wild, along with every card of the rank dealt face up right
+/// after the last queen dealt face up. A queen as the last up card leaves
+/// only the queens wild.
+pub struct FollowTheQueen;
+
+impl UpCardRule for FollowTheQueen {
+    fn wild_cards(&self, up_cards: &[&Card]) -> CardSet {
+        let queens = cards_of_rank(Rank::Queen);
+        let follower = up_cards.iter()
+            .rposition(|card| card.rank == Rank::Queen)
+            .and_then(|index| up_cards.get(index + 1))
+            .filter(|card| !Card::is_joker(card));
+
+        match follower {
+            Some(card) => queens | cards_of_rank(card.rank),
+            None => queens
+        }
+    }
+}
+
+/// The cards that are wild in a game and the number of jokers added to the
+/// deck. Fixed rules reduce to a set of wild cards, so they combine by
+/// taking the union; per-hand and up card rules add to that set for each
+/// deal.
+#[derive(Clone, Default)]
+pub struct WildCards {
+    pub cards: CardSet,
+    pub jokers: usize,
+    hand_rules: Vec<Arc<dyn WildRule>>,
+    up_card_rules: Vec<Arc<dyn UpCardRule>>
+}
+
+impl WildCards {
+    /// No wild cards and no jokers.
+    pub fn new() -> WildCards {
+        WildCards {
+            cards: CardSet::new(),
+            jokers: 0,
+            hand_rules: Vec::new(),
+            up_card_rules: Vec::new()
+        }
+    }
+
+    /// Adds `jokers` wild jokers to the deck.
+    pub fn with_jokers(mut self, jokers: usize) -> Self {
+        self.jokers = jokers;
+        if jokers > 0 {
+            self.cards |= cards_matching(Card::is_joker);
+        }
+        self
+    }
+
+    /// Makes every card matching `is_wild` wild, e.g. `Card::is_one_eyed_jack`.
+    pub fn with_rule(mut self, is_wild: IsWildCard) -> Self {
+        self.cards |= cards_matching(is_wild);
+        self
+    }
+
+    /// Makes each of `cards` wild.
+    pub fn with_cards(mut self, cards: &[Card]) -> Self {
+        self.cards |= CardSet::from(cards);
+        self
+    }
+
+    /// Adds a rule evaluated separately for each player's hand.
+    pub fn with_hand_rule<R: WildRule + 'static>(mut self, rule: R) -> Self {
+        self.hand_rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Adds a stud rule evaluated on the up cards dealt to the whole table.
+    pub fn with_up_card_rule<R: UpCardRule + 'static>(mut self, rule: R) -> Self {
+        self.up_card_rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Adds the rule called `name`: `deuces`, `one-eyed-jacks`,
+    /// `suicide-king`, `baseball`, `low-hole` or `follow-the-queen`.
+    pub fn with_named_rule(self, name: &str) -> Result<Self, ParseError> {
+        let is_wild: IsWildCard = match name.to_ascii_lowercase().as_str() {
+            "deuces" => Card::is_deuce,
+            "one-eyed-jacks" => Card::is_one_eyed_jack,
+            "suicide-king" => Card::is_suicide_king,
+            "baseball" => Card::is_baseball_wild,
+            "low-hole" => return Ok(self.with_hand_rule(LowHoleCard)),
+            "follow-the-queen" => return Ok(self.with_up_card_rule(FollowTheQueen)),
+            _ => return Err("Invalid wild card rule")
+        };
+        Ok(self.with_rule(is_wild))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty() && self.hand_rules.is_empty() && self.up_card_rules.is_empty()
+    }
+
+    /// Whether any rule depends on the hand, and so possibly on its suits.
+    pub fn has_hand_rules(&self) -> bool {
+        !self.hand_rules.is_empty()
+    }
+
+    /// Whether any rule depends on the order of the up cards, which only
+    /// stud deals.
+    pub fn has_up_card_rules(&self) -> bool {
+        !self.up_card_rules.is_empty()
+    }
+
+    /// The cards wild for every player given the table's up cards in the
+    /// order they were dealt, on top of `cards`.
+    pub fn for_up_cards(&self, up_cards: &[&Card]) -> CardSet {
+        self.up_card_rules.iter()
+            .fold(self.cards, |wild, rule| wild | rule.wild_cards(up_cards))
+    }
+
+    /// The cards wild for the player holding `hole` and playing with `board`.
+    pub fn for_hand(&self, hole: &[Card], board: &[&Card]) -> CardSet {
+        self.hand_rules.iter()
+            .fold(self.cards, |wild, rule| wild | rule.wild_cards(hole, board))
+    }
+
+    /// Adds this game's jokers to `deck`, less any already among the `known` cards.
+    pub fn add_jokers(&self, deck: &mut Vec<Card>, known: &[Card]) {
+        let dealt = known.iter().filter(|card| Card::is_joker(card)).count();
+        for _i in dealt..self.jokers {
+            deck.push(Card::new(Rank::Joker, Suit::Joker));
+        }
+    }
+
+    /// Best hand from all of `hole` and `board`.
+    pub fn build(&self, hole: &[Card], board: &[&Card]) -> PokerHand {
+        let wild = self.for_hand(hole, board);
+        let cards = hole.iter().chain(board.iter().cloned()).collect::<Vec<_>>();
+        PokerHand::build_with(&cards, &|card: &Card| wild.contains(card))
+    }
+
+    /// Best Omaha hand from two of `hole` and three of `board`.
+    pub fn build_omaha(&self, hole: &[Card], board: &[&Card]) -> PokerHand {
+        let wild = self.for_hand(hole, board);
+        PokerHand::build_omaha_with(&hole.iter().collect::<Vec<_>>(), board, &|card: &Card| wild.contains(card))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardVector;
+    use crate::poker_hand::HandRank;
+
+    fn build(wild: &WildCards, hole: &str, board: &str) -> PokerHand {
+        let board = CardVector::parse(board);
+        wild.build(&CardVector::parse(hole), &board.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_rules() {
+        let wild = WildCards::new().with_named_rule("deuces").unwrap();
+        assert_eq!(wild.cards.len(), 4);
+        assert_eq!(build(&wild, "Ac Ad", "2h 2s Kc").rank, HandRank::Quads);
+
+        let wild = WildCards::new().with_rule(Card::is_one_eyed_jack).with_rule(Card::is_suicide_king);
+        assert_eq!(wild.cards.len(), 3);
+        assert!(wild.cards.contains(&CardVector::parse("Kh")[0]));
+        assert!(!wild.cards.contains(&CardVector::parse("Kd")[0]));
+
+        assert!(WildCards::new().with_named_rule("sevens").is_err());
+        assert!(WildCards::new().is_empty());
+    }
+
+    #[test]
+    fn test_specific_cards() {
+        let wild = WildCards::new().with_cards(&CardVector::parse("7c 7d"));
+        assert_eq!(build(&wild, "Ac Ad", "Ah 7c 7d").rank, HandRank::FiveOfAKind);
+        assert_eq!(build(&WildCards::new(), "Ac Ad", "Ah 7c 7d").rank, HandRank::FullHouse);
+    }
+
+    #[test]
+    fn test_jokers() {
+        let wild = WildCards::new().with_jokers(2);
+        let mut deck = CardVector::parse("Ac Kc").to_vec();
+        wild.add_jokers(&mut deck, &CardVector::parse("Qd ??"));
+        assert_eq!(deck.len(), 3);
+        assert!(Card::is_joker(&deck[2]));
+        assert_eq!(build(&wild, "Ac Ad", "Ah ?? Kd").rank, HandRank::Quads);
+    }
+
+    #[test]
+    fn test_low_hole_card() {
+        let wild = WildCards::new().with_named_rule("low-hole").unwrap();
+        assert!(!wild.is_empty());
+
+        // The 3c and the 3h on the board are wild for the first player only
+        let board = "3h Ks Qd 7c 9s";
+        assert_eq!(build(&wild, "3c Ad", board).rank, HandRank::Straight);
+        assert_eq!(build(&wild, "Kc Kd", board).rank, HandRank::Quads);
+        assert_eq!(build(&wild, "3c 3d", board).rank, HandRank::StraightFlush);
+        assert_eq!(build(&WildCards::new(), "3c Ad", board).rank, HandRank::OnePair);
+    }
+
+    #[test]
+    fn test_follow_the_queen() {
+        let wild = WildCards::new().with_named_rule("follow-the-queen").unwrap();
+        assert!(wild.has_up_card_rules());
+        let for_up_cards = |up_cards: &str| {
+            let up_cards = CardVector::parse(up_cards);
+            wild.for_up_cards(&up_cards.iter().collect::<Vec<_>>())
+        };
+
+        assert_eq!(for_up_cards("7c 9d"), cards_of_rank(Rank::Queen));
+        assert_eq!(for_up_cards("7c Qd 9d 4h"), cards_of_rank(Rank::Queen) | cards_of_rank(Rank::Nine));
+        // A later queen replaces the earlier follower, and one dealt last
+        // leaves only the queens
+        assert_eq!(for_up_cards("Qc 9d Qh 4h"), cards_of_rank(Rank::Queen) | cards_of_rank(Rank::Four));
+        assert_eq!(for_up_cards("Qc 9d 4h Qh"), cards_of_rank(Rank::Queen));
+    }
+
+    #[test]
+    fn test_closure_rule() {
+        // Cards matching the rank of the player's first hole card are wild
+        let wild = WildCards::new().with_hand_rule(|hole: &[Card], board: &[&Card]| {
+            hole.iter()
+                .chain(board.iter().cloned())
+                .filter(|card| card.rank == hole[0].rank)
+                .collect::<CardSet>()
+        });
+        assert_eq!(build(&wild, "8c Ad", "8h Ah As").rank, HandRank::FiveOfAKind);
+        assert_eq!(build(&wild, "Ad 8c", "8h Ah As").rank, HandRank::FiveOfAKind);
+        assert_eq!(build(&wild, "Ad 8c", "8h 7h 2s").rank, HandRank::Triplets);
+    }
+}
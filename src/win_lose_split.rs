@@ -1,3 +1,4 @@
+#[derive(Copy, Clone, Default)]
 pub struct WinLoseSplit {
     pub wins: i32,
     pub losses: i32,
@@ -28,17 +29,24 @@ impl WinLoseSplit {
     pub fn splits_pct(&self) -> f32 {
         100.0 * (self.splits as f32) / (self.total() as f32)
     }
-}
 
-impl Copy for WinLoseSplit {}
+    pub fn win_std_error(&self) -> f32 {
+        let n = self.total() as f32;
+        let p = (self.wins as f32) / n;
+        100.0 * (p * (1.0 - p) / n).sqrt()
+    }
 
-impl Clone for WinLoseSplit {
-    fn clone(&self) -> Self {
-        WinLoseSplit {
-            wins: self.wins,
-            losses: self.losses,
-            splits: self.splits
-        }
+    pub fn win_confidence_interval(&self, z: f32) -> (f32, f32) {
+        let margin = z * self.win_std_error();
+        (self.win_pct() - margin, self.win_pct() + margin)
+    }
+}
+
+impl std::ops::AddAssign for WinLoseSplit {
+    fn add_assign(&mut self, other: WinLoseSplit) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.splits += other.splits;
     }
 }
 
@@ -47,3 +55,27 @@ impl std::fmt::Display for WinLoseSplit {
         write!(f, "{:.2}% - {:.2}% - {:.2}%", self.win_pct(), self.losses_pct(), self.splits_pct())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentages() {
+        let result = WinLoseSplit { wins: 50, losses: 25, splits: 25 };
+        assert_eq!(result.total(), 100);
+        assert_eq!(result.win_pct(), 50.0);
+        assert_eq!(result.losses_pct(), 25.0);
+        assert_eq!(result.splits_pct(), 25.0);
+    }
+
+    #[test]
+    fn test_win_std_error() {
+        let result = WinLoseSplit { wins: 50, losses: 50, splits: 0 };
+        assert!((result.win_std_error() - 5.0).abs() < 1e-4);
+
+        let (low, high) = result.win_confidence_interval(1.96);
+        assert!((low - 40.2).abs() < 1e-3);
+        assert!((high - 59.8).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,66 @@
+//! Runs the `poker_rust` binary on bad input and checks it reports an
+//! error instead of printing results.
+
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_poker_rust"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn assert_rejected(args: &[&str]) {
+    let output = run(args);
+    assert!(!output.status.success(), "{:?} succeeded", args);
+    assert!(output.stdout.is_empty(), "{:?} printed results", args);
+    assert!(!output.stderr.is_empty(), "{:?} printed no error", args);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"), "{:?} panicked", args);
+}
+
+#[test]
+fn test_zero_trials() {
+    assert_rejected(&["montecarlo", "AcAd", "KcKd", "-n", "0"]);
+    assert_rejected(&["AcAd", "KcKd", "--sample", "-n", "0"]);
+    assert!(run(&["montecarlo", "AcAd", "KcKd", "-n", "10"]).status.success());
+}
+
+#[test]
+fn test_invalid_stud_cards() {
+    assert_rejected(&["stud", "AcZd/Kh", "QsQh/2c"]);
+    assert_rejected(&["stud", "AcKd/Kh", "QsQh/2c", "-d", "Zz"]);
+}
+
+#[test]
+fn test_invalid_draw_cards() {
+    assert_rejected(&["draw", "7c5z4h/2", "KcKd/3"]);
+    assert_rejected(&["discard", "AcKdQh2s2z"]);
+    assert_rejected(&["discard", "AcKdQh2s2c", "-o", "KcKx/3"]);
+}
+
+#[test]
+fn test_invalid_wild_cards() {
+    assert_rejected(&["AcAd", "KcKd", "--wild-cards", "7c7z"]);
+    assert_rejected(&["stud", "AcKd/Kh", "QsQh/2c", "--wild-cards", "Xc"]);
+}
+
+#[test]
+fn test_invalid_pocket_cards() {
+    assert_rejected(&["AcZd", "KdKc"]);
+    assert_rejected(&["montecarlo", "AcAd", "Kd1c"]);
+    assert!(run(&["AcAd", "xx", "-n", "10", "--sample"]).status.success());
+}
+
+#[test]
+fn test_cards_dealt_twice() {
+    assert_rejected(&["AcAd", "AcKd"]);
+    assert_rejected(&["AcAd", "KcKd", "-b", "Ac2d3h"]);
+    assert_rejected(&["montecarlo", "AcAd", "KcKd", "QcQd", "-b", "2cQd3h"]);
+    assert!(run(&["AcAd", "??Kd", "??Kc", "--jokers", "2", "-b", "2c3d4h5s"]).status.success());
+}
+
+#[test]
+fn test_follow_the_queen_outside_stud() {
+    assert_rejected(&["AcAd", "KcKd", "--wild", "follow-the-queen"]);
+    assert!(run(&["stud", "AcAd3h/9c5h7d6s", "KcKd2h/Qs8s4c", "--wild", "follow-the-queen"]).status.success());
+}